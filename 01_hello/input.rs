@@ -1,16 +1,18 @@
 use remawin::{ActionMetadata, ActionArgument, MappedType};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum InputContext {
     Default
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Action {
     Exit,
     MoveForward,
     FireAbility1,
-    RotateDirection
+    RotateDirection,
+    Zoom,
+    NextAbility
 }
 
 impl ActionMetadata for Action {
@@ -19,7 +21,9 @@ impl ActionMetadata for Action {
             &Action::Exit => MappedType::Action,
             &Action::MoveForward => MappedType::State,
             &Action::FireAbility1 => MappedType::Action,
-            &Action::RotateDirection => MappedType::Range
+            &Action::RotateDirection => MappedType::Range,
+            &Action::Zoom => MappedType::Range,
+            &Action::NextAbility => MappedType::Action
         }
     }
 
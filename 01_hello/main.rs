@@ -3,6 +3,7 @@ extern crate remawin;
 extern crate glutin;
 extern crate time;
 extern crate serde;
+extern crate amethyst_experiments;
 
 #[macro_use]
 extern crate serde_derive;
@@ -15,10 +16,10 @@ use amethyst::renderer::Pipeline;
 use amethyst::config::Config;
 use amethyst::WindowEvent;
 
+use amethyst_experiments::AmethystEventMapper;
 use input::{InputContext, Action};
 
 mod input;
-mod input_mapper;
 
 struct Hello;
 
@@ -38,7 +39,13 @@ impl State for Hello {
                      _: &mut AssetManager,
                      _: &mut Pipeline)
                      -> Trans {
-        let mut input = world.write_resource::<input_mapper::AmethystEventMapper<Action, InputContext>>();
+        use amethyst::ecs::resources::ScreenDimensions;
+
+        let mut input = world.write_resource::<AmethystEventMapper<Action, InputContext>>();
+        {
+            let dim = world.read_resource::<ScreenDimensions>();
+            input.set_reported_size((dim.w as f64, dim.h as f64));
+        }
         let mapped_events = input.process(&events.to_vec());
         if mapped_events.len() > 0 {
             println!("{:?}", mapped_events);
@@ -64,10 +71,12 @@ fn main() {
     let cfg = DisplayConfig::load(path);
     let dim = cfg.dimensions.unwrap().clone();
     let mut game = Application::build(Hello, cfg).done();
-    let mut event_mapper = input_mapper::AmethystEventMapper::<Action, InputContext>::new(
+    let mut event_mapper = AmethystEventMapper::<Action, InputContext>::new(
         (dim.0 as f64, dim.1 as f64));
+    if let Err(err) = event_mapper.load_bindings(&input_path) {
+        println!("Invalid input bindings:\n{}", err);
+    }
     event_mapper.remapper_mut()
-        .with_bindings_from_file(&input_path)
         .activate_context(&InputContext::Default, 1);
     game.world_mut().add_resource(event_mapper);
     game.run();
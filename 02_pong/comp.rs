@@ -1,8 +1,14 @@
 use cgmath::{Vector2, Point2};
 use amethyst::ecs::{VecStorage, Component};
 
+pub use amethyst_experiments::coll::Side;
+
+use config::PaddleControl;
+
 pub struct Ball {
     pub position : Point2<f32>,
+    /// Position before the last physics step, for interpolating between steps.
+    pub previous_position : Point2<f32>,
     pub velocity : Vector2<f32>,
     pub radius : f32
 }
@@ -11,6 +17,7 @@ impl Ball {
     pub fn new() -> Ball {
         Ball {
             position: Point2::new(0.0, 0.0),
+            previous_position: Point2::new(0.0, 0.0),
             velocity: Vector2::new(0.0, 0.0),
             radius: 0.02
         }
@@ -21,33 +28,32 @@ impl Component for Ball {
     type Storage = VecStorage<Ball>;
 }
 
-#[derive(PartialEq, Clone, Debug)]
-pub enum Side {
-    Left,
-    Right
-}
-
-pub enum Direction {
-    Up,
-    Down
-}
-
 pub struct Plank {
     pub position : f32,
+    /// Position before the last physics step, for interpolating between steps.
+    pub previous_position : f32,
+    /// Height the plank moves towards, for controls that point at where it should be.
+    pub target : Option<f32>,
     pub velocity_up : f32,
     pub velocity_down : f32,
     pub dimensions : Vector2<f32>,
-    pub side: Side
+    pub side: Side,
+    pub player: usize,
+    pub control: PaddleControl
 }
 
 impl Plank {
-    pub fn new(side : Side) -> Plank {
+    pub fn new(side : Side, player : usize, control : PaddleControl) -> Plank {
         Plank {
             position : 0.0,
+            previous_position : 0.0,
+            target : None,
             velocity_down : 0.,
             velocity_up : 0.,
             dimensions : Vector2::new(0.01, 0.3),
-            side : side
+            side : side,
+            player : player,
+            control : control
         }
     }
 }
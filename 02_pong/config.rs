@@ -0,0 +1,96 @@
+use ron;
+use serde::de::DeserializeOwned;
+
+use std::fs::File;
+use std::io::Read;
+
+/// How a paddle is moved.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum PaddleControl {
+    /// The player's `PaddleMove` axis, from keys or a gamepad stick.
+    Axis,
+    /// Follows the cursor's height.
+    Cursor,
+    /// Moves with the vertical mouse motion.
+    Motion,
+    /// Follows a finger in the touch region holding down the given button.
+    Touch(u32)
+}
+
+/// Per side control settings, read from `controls.ron`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ControlConfig {
+    pub left : PaddleControl,
+    pub right : PaddleControl
+}
+
+impl Default for ControlConfig {
+    fn default() -> ControlConfig {
+        ControlConfig {
+            left : PaddleControl::Axis,
+            right : PaddleControl::Axis
+        }
+    }
+}
+
+impl ControlConfig {
+    /// Loads the control settings, falling back to the default on any error.
+    pub fn load(path : &str) -> ControlConfig {
+        load_or_default(path, "controls")
+    }
+}
+
+/// Tuning of the simulation and the ball's bounce off the planks, read from `rules.ron`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RulesConfig {
+    /// Outgoing angle from the horizontal, in degrees, for a hit at the very end of a plank.
+    /// Hits closer to the centre leave at proportionally flatter angles.
+    pub max_bounce_angle : f32,
+    /// Share of the plank's vertical velocity added to the ball on a hit, 0 for no spin.
+    pub spin : f32,
+    /// Physics steps per second. The simulation only ever advances by whole steps, so the
+    /// outcome does not depend on the frame rate.
+    pub tick_rate : u32,
+    /// Seed for the serve directions, the same seed serves the same sequence of balls.
+    pub seed : u32
+}
+
+impl Default for RulesConfig {
+    fn default() -> RulesConfig {
+        RulesConfig {
+            max_bounce_angle : 60.0,
+            spin : 0.0,
+            tick_rate : 120,
+            seed : 1
+        }
+    }
+}
+
+impl RulesConfig {
+    /// Loads the rules, falling back to the default on any error.
+    pub fn load(path : &str) -> RulesConfig {
+        load_or_default(path, "rules")
+    }
+
+    /// Length of a physics step in seconds.
+    pub fn tick_length(&self) -> f32 {
+        1.0 / self.tick_rate.max(1) as f32
+    }
+}
+
+fn load_or_default<T>(path : &str, what : &str) -> T
+    where T: DeserializeOwned + Default {
+    let mut data = String::new();
+    let loaded = File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut data))
+        .map_err(|err| format!("{}", err))
+        .and_then(|_| ron::de::from_str(&data).map_err(|err| format!("{:?}", err)));
+    match loaded {
+        Ok(config) => config,
+        Err(err) => {
+            println!("Failed loading {} from {}, using the defaults: {}", what, path, err);
+            T::default()
+        }
+    }
+}
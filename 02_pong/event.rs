@@ -1,40 +1,47 @@
+use amethyst_experiments::event;
+use amethyst_experiments::AmethystEventMapper;
+use shrev::EventHandler;
 use remawin;
-use shrev::{Event, EventHandler};
 
 use input::{Action, InputContext};
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct IWindowEvent {
-    pub payload : remawin::WindowEvent
-}
+pub use amethyst_experiments::event::IWindowEvent;
 
-impl IWindowEvent {
-    pub fn new(event: remawin::WindowEvent) -> IWindowEvent {
-        IWindowEvent {
-            payload : event
-        }
-    }
-}
+pub type ControllerEvent = event::ControllerEvent<Action, InputContext>;
+pub type ComboEvent = amethyst_experiments::combo::ComboEvent<Action>;
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct ControllerEvent {
-    pub payload : remawin::ControllerEvent<Action, InputContext>
+pub fn init_event_system() -> EventHandler {
+    event::init_event_system::<Action, InputContext>()
 }
 
-impl ControllerEvent {
-    pub fn new(event: remawin::ControllerEvent<Action, InputContext>) -> ControllerEvent {
-        ControllerEvent {
-            payload : event
+/// Writes a frame of mapped input, and the combos, axis changes and text input it completed, to
+/// the event handler for the systems. Returns true if the player asked to quit or the window was closed.
+pub fn write_input(input : &mut AmethystEventMapper<Action, InputContext>,
+                   mapped : Vec<(Option<usize>, remawin::Event<Action, InputContext>)>,
+                   event_handler : &mut EventHandler) -> bool {
+    let mut quit = false;
+    for (player, me) in mapped {
+        match me {
+            remawin::Event::Controller(remawin::ControllerEvent::Action(Action::Exit, _)) |
+            remawin::Event::Window(remawin::WindowEvent::Close) => {
+                quit = true;
+            },
+            remawin::Event::Window(event) => {
+                event_handler.write_single(IWindowEvent::new(event)).expect("Failed writing event to handler");
+            },
+            remawin::Event::Controller(event) => {
+                event_handler.write_single(ControllerEvent::for_player(player, event)).expect("Failed writing event to handler");
+            }
         }
     }
+    for combo in input.drain_combos() {
+        event_handler.write_single(combo).expect("Failed writing event to handler");
+    }
+    for axis in input.drain_axes() {
+        event_handler.write_single(axis).expect("Failed writing event to handler");
+    }
+    for text in input.drain_text_events() {
+        event_handler.write_single(text).expect("Failed writing event to handler");
+    }
+    quit
 }
-
-impl Event for ControllerEvent {}
-impl Event for IWindowEvent {}
-
-pub fn init_event_system() -> EventHandler {
-    let mut event_handler = EventHandler::new();
-    event_handler.register::<IWindowEvent>();
-    event_handler.register::<ControllerEvent>();
-    event_handler
-}
\ No newline at end of file
@@ -0,0 +1,152 @@
+use amethyst::ecs::{Dispatcher, DispatcherBuilder, Fetch, World};
+use amethyst::ecs::components::LocalTransform;
+use amethyst::ecs::resources::Time;
+use remawin;
+use remawin::raw::RawInput;
+use shrev::EventHandler;
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use amethyst_experiments::{AmethystEventMapper, InputState, TextInput};
+use amethyst_experiments::recording::{InputRecording, InputReplay};
+
+use comp::*;
+use config::{ControlConfig, RulesConfig};
+use event::*;
+use input::*;
+use setup::{create_entities, pong_camera};
+use system::*;
+
+/// Runs the pong world and `PongSystem` without a window or renderer, one physics step per
+/// tick, for checking the rules without a GPU. Input is scripted per tick, either as raw input
+/// that goes through the mapper and bindings like in the game, or as controller events written
+/// straight to the event handler. Recordings play back on the tick clock, each recorded frame
+/// on the first tick at or after its recorded time, counted from the start of the replay.
+pub struct Headless {
+    world : World,
+    dispatcher : Dispatcher<'static, 'static>,
+    raw_input : BTreeMap<u64, Vec<RawInput>>,
+    events : BTreeMap<u64, Vec<ControllerEvent>>,
+    ticks : u64
+}
+
+impl Headless {
+    /// Sets up the world like the game does, for a window of `size` in pixels.
+    pub fn new(size : (f64, f64),
+               mut input : AmethystEventMapper<Action, InputContext>,
+               controls : ControlConfig,
+               rules : RulesConfig) -> Headless {
+        input.set_window_size(size);
+        input.push_context(InputContext::Default, 1);
+
+        let mut world = World::new();
+        world.register::<Ball>();
+        world.register::<Plank>();
+        world.register::<LocalTransform>();
+        world.add_resource(pong_camera((size.0 / size.1) as f32));
+        world.add_resource(Time::default());
+        world.add_resource(InputState::new(size));
+        world.add_resource(TextInput::new());
+        world.add_resource(GameState::new(rules.seed));
+        world.add_resource(rules);
+        world.add_resource(init_event_system());
+        world.add_resource(input);
+        create_entities(&mut world, &controls, |entity| entity);
+
+        let dispatcher = DispatcherBuilder::new()
+            .add(PongSystem::new(), "pong_system", &[])
+            .build();
+
+        Headless {
+            world : world,
+            dispatcher : dispatcher,
+            raw_input : BTreeMap::new(),
+            events : BTreeMap::new(),
+            ticks : 0
+        }
+    }
+
+    /// Queues raw input to be mapped at the start of `tick`.
+    pub fn push_raw_input(&mut self, tick : u64, raw_input : Vec<RawInput>) -> &mut Self {
+        self.raw_input.entry(tick).or_insert_with(Vec::new).extend(raw_input);
+        self
+    }
+
+    /// Queues a controller event to be written to the event handler at the start of `tick`.
+    pub fn push_event(&mut self,
+                      tick : u64,
+                      player : Option<usize>,
+                      event : remawin::ControllerEvent<Action, InputContext>) -> &mut Self {
+        self.events.entry(tick).or_insert_with(Vec::new).push(ControllerEvent::for_player(player, event));
+        self
+    }
+
+    /// Plays back a recording by its frame times in place of the queued raw input, with the
+    /// first recorded frame on the next tick.
+    pub fn replay(&mut self, recording : InputRecording) -> &mut Self {
+        self.world.write_resource::<AmethystEventMapper<Action, InputContext>>()
+            .start_replay(InputReplay::new(recording));
+        self
+    }
+
+    /// Simulates one tick. Returns false once the input asked to quit.
+    pub fn tick(&mut self) -> bool {
+        let tick = self.world.read_resource::<RulesConfig>().tick_length() as f64;
+        let now = self.ticks as f64 * tick;
+        let quit = {
+            let mut input = self.world.write_resource::<AmethystEventMapper<Action, InputContext>>();
+            let raw_input = self.raw_input.remove(&self.ticks).unwrap_or_default();
+            let mapped = if input.is_replaying() {
+                input.process_timed_players(&[], now)
+            } else {
+                input.process_raw_input_at(&raw_input, now)
+            };
+            let mut event_handler = self.world.write_resource::<EventHandler>();
+            let quit = write_input(&mut input, mapped, &mut event_handler);
+            for event in self.events.remove(&self.ticks).unwrap_or_default() {
+                event_handler.write_single(event).expect("Failed writing event to handler");
+            }
+            self.world.write_resource::<InputState>().clone_from(input.input_state());
+            self.world.write_resource::<TextInput>().clone_from(input.text_input());
+            quit
+        };
+
+        // frame times are whole nanoseconds, rounded up from the tick boundaries so that every
+        // frame holds exactly one physics step
+        let start = (self.ticks as f64 * tick * 1.0e9).ceil() as u64;
+        let end = ((self.ticks + 1) as f64 * tick * 1.0e9).ceil() as u64;
+        self.world.write_resource::<Time>().delta_time = Duration::new(0, (end - start) as u32);
+        self.dispatcher.dispatch(&mut self.world.res);
+        self.ticks += 1;
+        !quit
+    }
+
+    /// Simulates up to `ticks` ticks, stopping early if the input asks to quit. Returns the
+    /// number of ticks simulated.
+    pub fn run(&mut self, ticks : u64) -> u64 {
+        for ran in 0..ticks {
+            if !self.tick() {
+                return ran + 1;
+            }
+        }
+        ticks
+    }
+
+    /// Ticks simulated so far.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn game_state(&self) -> Fetch<GameState> {
+        self.world.read_resource::<GameState>()
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+}
@@ -1,31 +1,29 @@
 use remawin::{ActionMetadata, ActionArgument, MappedType, Context};
-use remawin::types::{RawType, RawArgs, KeyCode};
-use input_mapper::AmethystEventMapper;
+use remawin::types::{RawType, RawArgs, KeyCode, DeviceType};
+use amethyst_experiments::AmethystEventMapper;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum InputContext {
     Default
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Action {
     Exit,
-    LeftPaddleUp,
-    LeftPaddleDown,
-    RightPaddleUp,
-    RightPaddleDown,
-    StartRound
+    PaddleMove,
+    /// Bound to the release of Space rather than its press, so that holding Space for a
+    /// `PowerServe` does not start the round before the hold completes.
+    StartRound,
+    PowerServe
 }
 
 impl ActionMetadata for Action {
     fn mapped_type(&self) -> MappedType {
         match self {
             &Action::Exit => MappedType::Action,
-            &Action::LeftPaddleUp => MappedType::State,
-            &Action::LeftPaddleDown => MappedType::State,
-            &Action::RightPaddleUp => MappedType::State,
-            &Action::RightPaddleDown => MappedType::State,
-            &Action::StartRound => MappedType::Action
+            &Action::PaddleMove => MappedType::Range,
+            &Action::StartRound => MappedType::Action,
+            &Action::PowerServe => MappedType::Action
         }
     }
 
@@ -35,17 +33,51 @@ impl ActionMetadata for Action {
 }
 
 pub fn init_input_system(size : (f64, f64),
-                         input_path: &str) -> AmethystEventMapper<Action, InputContext> {
+                         input_path: &str,
+                         player_input_path: &str,
+                         overrides_path: &str,
+                         combos_path: &str,
+                         axes_path: &str,
+                         axis_overrides_path: &str,
+                         touch_regions_path: &str) -> AmethystEventMapper<Action, InputContext> {
     let mut event_mapper = AmethystEventMapper::<Action, InputContext>::new(size);
-    event_mapper.remapper_mut()
-        .with_bindings_from_file(input_path)
-        /*.with_context(Context::new(InputContext::Default)
+    event_mapper.with_user_overrides(overrides_path);
+    if let Err(err) = event_mapper.load_bindings(input_path) {
+        println!("Invalid input bindings:\n{}", err);
+    }
+    /*event_mapper.remapper_mut()
+        .with_context(Context::new(InputContext::Default)
             .with_mapping(RawType::Key, RawArgs::new().with_keycode(KeyCode::Escape), Action::Exit)
-            .with_mapping(RawType::Key, RawArgs::new().with_keycode(KeyCode::W), Action::LeftPaddleUp)
-            .with_mapping(RawType::Key, RawArgs::new().with_keycode(KeyCode::S), Action::LeftPaddleDown)
-            .with_mapping(RawType::Key, RawArgs::new().with_keycode(KeyCode::Up), Action::RightPaddleUp)
-            .with_mapping(RawType::Key, RawArgs::new().with_keycode(KeyCode::Down), Action::RightPaddleDown)
-            .with_mapping(RawType::Key, RawArgs::new().with_keycode(KeyCode::Space), Action::StartRound))*/
-        .activate_context(&InputContext::Default, 1);
+            .with_mapping(RawType::Key, RawArgs::new().with_keycode(KeyCode::Space), Action::StartRound));*/
+    event_mapper.watch_bindings();
+    if let Err(err) = event_mapper.load_combos(combos_path) {
+        println!("Invalid input combos:\n{}", err);
+    }
+
+    if let Err(err) = event_mapper.load_touch_regions(touch_regions_path) {
+        println!("Invalid touch regions:\n{}", err);
+    }
+
+    // one player per side, sharing the keyboard and each owning the gamepad with its index,
+    // whose bindings only see that player's devices
+    for _ in 0..2 {
+        let player = event_mapper.add_player();
+        event_mapper
+            .assign_device(player, DeviceType::Keyboard, 0)
+            .and_then(|mapper| mapper.assign_device(player, DeviceType::Gamepad, player as u32))
+            .expect("Player was just added");
+        if let Err(err) = event_mapper.load_player_bindings(player, player_input_path) {
+            println!("Invalid input bindings for player {}:\n{}", player, err);
+        }
+    }
+
+    // axes name their players, so they are loaded once the players exist, and the physics
+    // steps move them
     event_mapper
-}
\ No newline at end of file
+        .with_stepped_axes()
+        .with_user_axis_overrides(axis_overrides_path);
+    if let Err(err) = event_mapper.load_axes(axes_path) {
+        println!("Invalid input axes:\n{}", err);
+    }
+    event_mapper
+}
@@ -4,8 +4,9 @@ extern crate time;
 extern crate cgmath;
 extern crate shrev;
 extern crate rand;
-extern crate collision;
 extern crate serde;
+extern crate ron;
+extern crate amethyst_experiments;
 
 #[macro_use]
 extern crate serde_derive;
@@ -13,31 +14,41 @@ extern crate serde_derive;
 use amethyst::{Application, State, Trans};
 use amethyst::asset_manager::AssetManager;
 use amethyst::ecs::World;
-use amethyst::ecs::components::{Mesh, LocalTransform, Texture, Transform};
+use amethyst::ecs::components::{Mesh, Texture, Transform};
 use amethyst::ecs::systems::TransformSystem;
 use amethyst::gfx_device::DisplayConfig;
 use amethyst::renderer::{Pipeline, VertexPosNormal};
 use amethyst::config::Config;
 use amethyst::WindowEvent;
 
+use std::env;
+
 mod input;
-mod input_mapper;
+mod config;
 mod comp;
-mod coll;
 mod event;
 mod system;
+mod setup;
 
 use comp::*;
+use config::{ControlConfig, RulesConfig};
 use event::*;
-use input_mapper::*;
+use amethyst_experiments::{AmethystEventMapper, ContextState, InputMetrics, InputState, TextInput};
+use amethyst_experiments::recording::{InputRecording, InputReplay};
+#[cfg(feature = "gilrs")]
+use amethyst_experiments::gamepad::GilrsSource;
 use input::*;
 use system::*;
+use setup::{create_entities, pong_camera};
 
-struct Pong;
+struct Pong {
+    /// Where to save the input recorded this session, on exit.
+    record_path : Option<String>
+}
 
 impl State for Pong {
     fn on_start(&mut self, world : &mut World, assets : &mut AssetManager, pipe : &mut Pipeline) {
-        use amethyst::ecs::resources::{Camera, Projection, ScreenDimensions};
+        use amethyst::ecs::resources::{Camera, ScreenDimensions};
         use amethyst::renderer::Layer;
         use amethyst::renderer::pass::{Clear, DrawFlat};
 
@@ -47,40 +58,14 @@ impl State for Pong {
         pipe.layers.push(layer);
 
         // Setup camera
-        let (left_bound, right_bound) = {
+        {
             let dim = world.read_resource::<ScreenDimensions>();
             let mut camera = world.write_resource::<Camera>();
-            let aspect_ratio = dim.aspect_ratio;
-            let eye = [0., 0., 0.1];
-            let target = [0., 0., 0.];
-            let up = [0., 1., 0.];
-
-            // Get an Orthographic projection
-            let proj = Projection::Orthographic {
-                left: -1.0 * aspect_ratio,
-                right: 1.0 * aspect_ratio,
-                bottom: -1.0,
-                top: 1.0,
-                near: 0.0,
-                far: 1.0,
-            };
-
-            camera.proj = proj;
-            camera.eye = eye;
-            camera.target = target;
-            camera.up = up;
-
-            match camera.proj {
-                Projection::Orthographic {
-                    left,
-                    right,
-                    ..
-                } => (left, right),
-                _ => (1.0, 1.0),
-            }
-        };
+            *camera = pong_camera(dim.aspect_ratio);
+        }
 
-        world.add_resource::<GameState>(GameState::new());
+        let seed = world.read_resource::<RulesConfig>().seed;
+        world.add_resource::<GameState>(GameState::new(seed));
 
         assets.register_asset::<Mesh>();
         assets.register_asset::<Texture>();
@@ -92,49 +77,11 @@ impl State for Pong {
             .create_renderable("square", "white", "white", "white", 1.0)
             .unwrap();
 
-        let ball = Ball::new();
-        let mut local = LocalTransform::default();
-        local.translation = [ball.position.x, ball.position.y, 0.0];
-        local.scale = [ball.radius, ball.radius, 1.0];
-        world
-            .create_entity()
-            .with(square.clone())
-            .with(ball)
-            .with(local)
-            .with(Transform::default())
-            .build();
-
-        let plank = Plank::new(Side::Left);
-        let mut local = LocalTransform::default();
-        local.scale = [plank.dimensions.x, plank.dimensions.y, 1.0];
-        match plank.side {
-            Side::Left => local.translation = [left_bound + plank.dimensions.x/2., plank.position, 0.0],
-            Side::Right => local.translation = [right_bound - plank.dimensions.x/2., plank.position, 0.0],
-        };
-        world
-            .create_entity()
-            .with(square.clone())
-            .with(plank)
-            .with(local)
-            .with(Transform::default())
-            .build();
-
-        // Create right plank entity
-        let plank = Plank::new(Side::Right);
-        let mut local = LocalTransform::default();
-        local.scale = [plank.dimensions.x, plank.dimensions.y, 1.0];
-        match plank.side {
-            Side::Left => local.translation = [left_bound + plank.dimensions.x/2., plank.position, 0.0],
-            Side::Right => local.translation = [right_bound - plank.dimensions.x/2., plank.position, 0.0],
-        };
-        world
-            .create_entity()
-            .with(square.clone())
-            .with(plank)
-            .with(local)
-            .with(Transform::default())
-            .build();
-
+        let controls = world.read_resource::<ControlConfig>().clone();
+        create_entities(world, &controls, |entity| {
+            entity.with(square.clone())
+                .with(Transform::default())
+        });
     }
 
     fn handle_events(&mut self,
@@ -143,21 +90,44 @@ impl State for Pong {
                      _: &mut AssetManager,
                      _: &mut Pipeline)
                      -> Trans {
+        use amethyst::Event;
+        use amethyst::ecs::resources::ScreenDimensions;
+
         let mut input = world.write_resource::<AmethystEventMapper<Action, InputContext>>();
         let mut event_handler = world.write_resource::<shrev::EventHandler>();
-        for me in input.process(&events.to_vec()) {
-            match me {
-                remawin::Event::Controller(remawin::ControllerEvent::Action(Action::Exit, _)) |
-                remawin::Event::Window(remawin::WindowEvent::Close) => {
-                    return Trans::Quit;
-                },
-                remawin::Event::Window(event) => {
-                    event_handler.write_single(IWindowEvent::new(event)).expect("Failed writing event to handler");
-                },
-                remawin::Event::Controller(event) => {
-                    event_handler.write_single(ControllerEvent::new(event)).expect("Failed writing event to handler");
-                }
+        {
+            let dim = world.read_resource::<ScreenDimensions>();
+            input.set_reported_size((dim.w as f64, dim.h as f64));
+        }
+
+        // a replay ignores live input, but the window can still be closed
+        let closed = input.is_replaying() && events.iter().any(|e| match e.payload {
+            Event::Closed => true,
+            _ => false
+        });
+        let mapped = input.process_players(&events.to_vec());
+        let quit = write_input(&mut input, mapped, &mut event_handler) || closed;
+        match input.take_reload_result() {
+            Some(Ok(())) => println!("Reloaded input bindings"),
+            Some(Err(err)) => println!("Failed reloading input bindings, keeping the current ones:\n{}", err),
+            None => ()
+        };
+        world.write_resource::<InputState>().clone_from(input.input_state());
+        world.write_resource::<TextInput>().clone_from(input.text_input());
+        if let Some(metrics) = input.metrics() {
+            world.write_resource::<InputMetrics>().clone_from(metrics);
+        }
+        if quit {
+            if let Some(metrics) = input.metrics() {
+                println!("Input metrics:\n{}", metrics);
             }
+            if let (Some(recording), Some(path)) = (input.stop_recording(), self.record_path.as_ref()) {
+                match recording.save(path) {
+                    Ok(_) => println!("Saved input recording to {}", path),
+                    Err(err) => println!("Failed saving input recording to {}: {:?}", path, err)
+                };
+            }
+            return Trans::Quit;
         }
         Trans::None
     }
@@ -169,19 +139,84 @@ fn main() {
                        env!("CARGO_MANIFEST_DIR"));
     let input_path = format!("{}/02_pong/resources/bindings.ron",
                        env!("CARGO_MANIFEST_DIR"));
+    let player_input_path = format!("{}/02_pong/resources/bindings_player.ron",
+                       env!("CARGO_MANIFEST_DIR"));
+    let overrides_path = format!("{}/02_pong/resources/bindings_user.ron",
+                       env!("CARGO_MANIFEST_DIR"));
+    let combos_path = format!("{}/02_pong/resources/combos.ron",
+                       env!("CARGO_MANIFEST_DIR"));
+    let axes_path = format!("{}/02_pong/resources/axes.ron",
+                       env!("CARGO_MANIFEST_DIR"));
+    let axis_overrides_path = format!("{}/02_pong/resources/axes_user.ron",
+                       env!("CARGO_MANIFEST_DIR"));
+    let controls_path = format!("{}/02_pong/resources/controls.ron",
+                       env!("CARGO_MANIFEST_DIR"));
+    let touch_regions_path = format!("{}/02_pong/resources/touch_regions.ron",
+                       env!("CARGO_MANIFEST_DIR"));
+    let rules_path = format!("{}/02_pong/resources/rules.ron",
+                       env!("CARGO_MANIFEST_DIR"));
     let cfg = DisplayConfig::load(path);
     let dim = cfg.dimensions.as_ref().unwrap().clone();
-    let mut game = Application::build(Pong, cfg)
+    // set PONG_RECORD to a path to record the input and save it there on exit, and
+    // PONG_REPLAY to a saved recording to play it back instead of live input
+    let record_path = env::var("PONG_RECORD").ok();
+    let replay_path = env::var("PONG_REPLAY").ok();
+    let pong = ContextState::<_, Action, InputContext>::new(Pong { record_path : record_path.clone() },
+                                                            InputContext::Default,
+                                                            1);
+    let mut game = Application::build(pong, cfg)
         .register::<Ball>()
         .register::<Plank>()
         .with::<PongSystem>(PongSystem::new(), "pong_system", &[])
         .with::<TransformSystem>(TransformSystem::new(), "transform_system", &["pong_system"])
         .done();
-    game.world_mut().add_resource(init_input_system((dim.0 as f64, dim.1 as f64), &input_path));
+    let mut event_mapper = init_input_system((dim.0 as f64, dim.1 as f64),
+                                             &input_path,
+                                             &player_input_path,
+                                             &overrides_path,
+                                             &combos_path,
+                                             &axes_path,
+                                             &axis_overrides_path,
+                                             &touch_regions_path);
+    connect_gamepads(&mut event_mapper);
+    // set PONG_INPUT_METRICS to collect input metrics, printed on exit
+    if env::var("PONG_INPUT_METRICS").is_ok() {
+        event_mapper.enable_metrics();
+    }
+    if record_path.is_some() {
+        event_mapper.start_recording();
+    }
+    if let Some(path) = replay_path {
+        match InputRecording::load(&path) {
+            Ok(recording) => event_mapper.start_replay(InputReplay::new(recording)),
+            Err(err) => println!("Failed loading input recording {}, playing live: {:?}", path, err)
+        };
+    }
+    game.world_mut().add_resource(event_mapper);
+    game.world_mut().add_resource(InputMetrics::new());
     game.world_mut().add_resource(init_event_system());
+    game.world_mut().add_resource(InputState::new((dim.0 as f64, dim.1 as f64)));
+    game.world_mut().add_resource(TextInput::new());
+    game.world_mut().add_resource(ControlConfig::load(&controls_path));
+    game.world_mut().add_resource(RulesConfig::load(&rules_path));
     game.run();
 }
 
+/// Reads real gamepads, which the headless harness leaves out to keep its input scripted.
+#[cfg(feature = "gilrs")]
+fn connect_gamepads(event_mapper : &mut AmethystEventMapper<Action, InputContext>) {
+    match GilrsSource::new() {
+        Ok(source) => {
+            event_mapper.with_gamepad_source(source);
+        },
+        Err(err) => println!("Playing without gamepads: {}", err)
+    };
+}
+
+#[cfg(not(feature = "gilrs"))]
+fn connect_gamepads(_ : &mut AmethystEventMapper<Action, InputContext>) {
+}
+
 fn gen_rectangle(w: f32, h: f32) -> Vec<VertexPosNormal> {
     let data: Vec<VertexPosNormal> = vec![
         VertexPosNormal {
@@ -0,0 +1,53 @@
+use amethyst::ecs::{EntityBuilder, World};
+use amethyst::ecs::components::LocalTransform;
+use amethyst::ecs::resources::{Camera, Projection};
+
+use comp::*;
+use config::ControlConfig;
+use system::Field;
+
+/// An orthographic camera showing the field, two units high and as wide as the window.
+pub fn pong_camera(aspect_ratio : f32) -> Camera {
+    Camera {
+        proj : Projection::Orthographic {
+            left: -1.0 * aspect_ratio,
+            right: 1.0 * aspect_ratio,
+            bottom: -1.0,
+            top: 1.0,
+            near: 0.0,
+            far: 1.0,
+        },
+        eye : [0., 0., 0.1],
+        target : [0., 0., 0.],
+        up : [0., 1., 0.]
+    }
+}
+
+/// Creates the ball and the two planks in the field seen by the world's camera. `decorate` adds
+/// anything else the entities need, like what to draw them with.
+pub fn create_entities<F>(world : &mut World, controls : &ControlConfig, decorate : F)
+    where F: Fn(EntityBuilder) -> EntityBuilder {
+    let field = Field::from_camera(&world.read_resource::<Camera>());
+
+    let ball = Ball::new();
+    let mut local = LocalTransform::default();
+    local.translation = [ball.position.x, ball.position.y, 0.0];
+    local.scale = [ball.radius, ball.radius, 1.0];
+    decorate(world.create_entity())
+        .with(ball)
+        .with(local)
+        .build();
+
+    let planks = vec![(Side::Left, 0, controls.left.clone()),
+                      (Side::Right, 1, controls.right.clone())];
+    for (side, player, control) in planks {
+        let plank = Plank::new(side, player, control);
+        let mut local = LocalTransform::default();
+        local.scale = [plank.dimensions.x, plank.dimensions.y, 1.0];
+        local.translation = [field.plank_x(&plank), plank.position, 0.0];
+        decorate(world.create_entity())
+            .with(plank)
+            .with(local)
+            .build();
+    }
+}
@@ -1,52 +1,115 @@
 use shrev::{EventHandler, ReaderId};
 use cgmath::{Point2, Vector2};
-use remawin::{StateAction};
 use remawin;
-use rand;
 
 use cgmath::InnerSpace;
 
-use rand::Rng;
+use rand::{Rng, SeedableRng, XorShiftRng};
 
 use amethyst::ecs::{Fetch, FetchMut, Join, System, WriteStorage};
 use amethyst::ecs::components::LocalTransform;
 use amethyst::ecs::resources::{Camera, Projection, Time};
 
+use amethyst_experiments::{AmethystEventMapper, InputState};
+use amethyst_experiments::cursor::cursor_to_world;
+
 use input::*;
 use comp::*;
-use coll::*;
+use config::{PaddleControl, RulesConfig};
+use amethyst_experiments::coll::*;
 use event::*;
 
 const PLANK_VELOCITY : f32 = 2.0;
 const BALL_VELOCITY : f32 = 0.6;
 const BALL_BOUNCE_VELOCITY_INCREASE : f32 = 1.2;
+const POWER_SERVE_VELOCITY_INCREASE : f32 = 1.5;
+/// Longest frame simulated in full, longer frames (after a stall) slow the game down instead
+/// of running many steps to catch up. The time left out adds up in `GameState::dropped_time`.
+const MAX_FRAME_TIME : f64 = 0.25;
 
 pub struct GameState {
     pub left_score : u32,
     pub right_score : u32,
     pub round_active : bool,
-    pub round : u32
+    pub round : u32,
+    pub power_serve : bool,
+    /// Physics steps simulated so far.
+    pub ticks : u64,
+    /// Seconds of frame time never simulated, because frames ran longer than the longest
+    /// frame simulated in full. The game lags behind the clock by this much.
+    pub dropped_time : f64,
+    rng : XorShiftRng
 }
 
 impl GameState {
-    pub fn new() -> GameState {
+    pub fn new(seed : u32) -> GameState {
         GameState {
             left_score : 0,
             right_score: 0,
             round_active : false,
-            round : 1
+            round : 1,
+            power_serve : false,
+            ticks : 0,
+            dropped_time : 0.0,
+            // xorshift needs a seed that is not all zeros
+            rng : XorShiftRng::from_seed([seed, 0x9e37_79b9, 0x243f_6a88, 0xb7e1_5162])
+        }
+    }
+}
+
+/// The playing field, from the camera's orthographic bounds.
+#[derive(Debug, Clone, Copy)]
+pub struct Field {
+    pub left : f32,
+    pub right : f32,
+    pub top : f32,
+    pub bottom : f32
+}
+
+impl Field {
+    pub fn from_camera(camera : &Camera) -> Field {
+        match camera.proj {
+            Projection::Orthographic { left, right, top, bottom, .. } => Field {
+                left : left,
+                right : right,
+                top : top,
+                bottom : bottom
+            },
+            _ => Field { left : 1.0, right : 1.0, top : 1.0, bottom : 1.0 }
+        }
+    }
+
+    /// Horizontal position of the centre of a plank.
+    pub fn plank_x(&self, plank : &Plank) -> f32 {
+        match plank.side {
+            Side::Left => self.left + plank.dimensions.x/2.,
+            Side::Right => self.right - plank.dimensions.x/2.
         }
     }
+
+    /// Limits a plank position to keep the plank inside the field.
+    pub fn clamp_plank(&self, plank : &Plank, position : f32) -> f32 {
+        position.min(self.top - plank.dimensions.y/2.).max(self.bottom + plank.dimensions.y/2.)
+    }
 }
 
 pub struct PongSystem {
-    reader_id : Option<ReaderId>
+    reader_id : Option<ReaderId>,
+    combo_reader_id : Option<ReaderId>,
+    accumulator : f64,
+    /// Cursor motion of the frames since the last physics step, in window heights.
+    motion : f64,
+    serve : bool
 }
 
 impl PongSystem {
     pub fn new() -> PongSystem {
         PongSystem {
-            reader_id : None
+            reader_id : None,
+            combo_reader_id : None,
+            accumulator : 0.0,
+            motion : 0.0,
+            serve : false
         }
     }
 }
@@ -57,13 +120,16 @@ impl<'a> System<'a> for PongSystem {
                        WriteStorage<'a, LocalTransform>,
                        Fetch<'a, Camera>,
                        Fetch<'a, Time>,
+                       Fetch<'a, InputState>,
+                       Fetch<'a, RulesConfig>,
                        FetchMut<'a, GameState>,
-                       FetchMut<'a, EventHandler>);
+                       FetchMut<'a, EventHandler>,
+                       FetchMut<'a, AmethystEventMapper<Action, InputContext>>);
 
     #[allow(unused_variables)]
     #[allow(unused_mut)]
     fn run(&mut self,
-           (mut balls, mut planks, mut locals, camera, time, mut game_state, mut events): Self::SystemData) {
+           (mut balls, mut planks, mut locals, camera, time, input, rules, mut game_state, mut events, mut mapper): Self::SystemData) {
         let mut reader_id = match self.reader_id {
             Some(reader_id) => reader_id,
             None => match events.register_reader::<ControllerEvent>() {
@@ -72,26 +138,13 @@ impl<'a> System<'a> for PongSystem {
             }
         };
 
-        // process plank controller input
-        let mut start_ball = false;
+        // process plank controller input, a serve waits for the next physics step
         for event in events.read::<ControllerEvent>(&mut reader_id).unwrap() {
             match event.payload {
-                remawin::ControllerEvent::State(action, state, _, _) => {
-                    if state == StateAction::Activated
-                        || state == StateAction::Deactivated {
-                        match action {
-                            Action::LeftPaddleDown => update_velocity(&mut planks, Side::Left, Direction::Down, state),
-                            Action::LeftPaddleUp => update_velocity(&mut planks, Side::Left, Direction::Up, state),
-                            Action::RightPaddleDown => update_velocity(&mut planks, Side::Right, Direction::Down, state),
-                            Action::RightPaddleUp => update_velocity(&mut planks, Side::Right, Direction::Up, state),
-                            _ => ()
-                        };
-                    }
-                }
                 remawin::ControllerEvent::Action(Action::StartRound, _) => {
                     if !game_state.round_active {
                         game_state.round_active = true;
-                        start_ball = true;
+                        self.serve = true;
                     };
                 },
                 _ => ()
@@ -99,144 +152,253 @@ impl<'a> System<'a> for PongSystem {
         }
         self.reader_id = Some(reader_id);
 
-        let (left_bound, right_bound, top_bound, bottom_bound) = match camera.proj {
-            Projection::Orthographic {
-                left,
-                right,
-                top,
-                bottom,
-                ..
-            } => (left, right, top, bottom),
-            _ => (1.0, 1.0, 1.0, 1.0),
+        // holding the serve key long enough powers up the next serve
+        let mut combo_reader_id = match self.combo_reader_id {
+            Some(reader_id) => reader_id,
+            None => match events.register_reader::<ComboEvent>() {
+                Ok(reader_id) => reader_id,
+                Err(err) => panic!("{:?}", err)
+            }
         };
+        for event in events.read::<ComboEvent>(&mut combo_reader_id).unwrap() {
+            match event.action {
+                Action::PowerServe => {
+                    if !game_state.round_active {
+                        game_state.power_serve = true;
+                    }
+                },
+                _ => ()
+            };
+        }
+        self.combo_reader_id = Some(combo_reader_id);
 
-        let dt = time.delta_time.subsec_nanos() as f32 / 1.0e9;
-
-        let mut plank_collision_data : Vec<PlankCollisionData> = Vec::default();
-        // update plank positions
-        // do plank/boundary collision testing
-        for (plank, mut local) in (&mut planks, &mut locals).join() {
+        let field = Field::from_camera(&camera);
 
-            // update plank position
-            plank.position += plank.velocity_up * dt;
-            plank.position -= plank.velocity_down * dt;
+        // run as many whole physics steps as the frame time covers, carrying the rest over
+        let tick = rules.tick_length();
+        let frame_time = time.delta_time.as_secs() as f64
+            + time.delta_time.subsec_nanos() as f64 / 1.0e9;
+        game_state.dropped_time += (frame_time - MAX_FRAME_TIME).max(0.0);
+        self.accumulator += frame_time.min(MAX_FRAME_TIME);
+        self.motion += input.motion().1;
+        while self.accumulator >= tick as f64 {
+            self.accumulator -= tick as f64;
 
-            // do boundary collision testing and response
-            if (plank.position + plank.dimensions.y/2.) >= top_bound {
-                plank.position = top_bound - plank.dimensions.y/2.;
-                plank.velocity_down = 0.;
-                plank.velocity_up = 0.;
+            // the paddle axes and pointer controls move with the physics steps, not the frames
+            for event in mapper.step_axes(tick) {
+                match (event.action, event.player) {
+                    (Action::PaddleMove, Some(player)) => update_velocity(&mut planks, player, event.value),
+                    _ => ()
+                };
             }
+            update_targets(&mut planks, &input, &camera, &field, self.motion);
+            self.motion = 0.0;
 
-            if (plank.position - plank.dimensions.y/2.) <= bottom_bound {
-                plank.position = bottom_bound + plank.dimensions.y/2.;
-                plank.velocity_up = 0.;
-                plank.velocity_down = 0.;
-            }
+            step(&mut balls, &mut planks, &field, tick, self.serve, &rules, &mut game_state);
+            self.serve = false;
+        }
 
-            // update transform
+        // draw everything part way between the last two steps
+        let alpha = (self.accumulator / tick as f64) as f32;
+        for (plank, mut local) in (&planks, &mut locals).join() {
+            let position = plank.previous_position + (plank.position - plank.previous_position) * alpha;
             local.scale = [plank.dimensions.x, plank.dimensions.y, 1.0];
-            match plank.side {
-                Side::Left => local.translation = [left_bound + plank.dimensions.x/2., plank.position, 0.0],
-                Side::Right => local.translation = [right_bound - plank.dimensions.x/2., plank.position, 0.0],
-            };
-
-            // store plank collision data for ball collision testing
-            let x = match plank.side {
-                Side::Left => left_bound + plank.dimensions.x,
-                Side::Right => right_bound - plank.dimensions.x
-            };
-            plank_collision_data.push(PlankCollisionData::new(plank.side.clone(),
-                                                              x,
-                                                              plank.position + plank.dimensions.y/2.,
-                                                              plank.position - plank.dimensions.y/2.));
+            local.translation = [field.plank_x(plank), position, 0.0];
         }
+        for (ball, mut local) in (&balls, &mut locals).join() {
+            let position = ball.previous_position + (ball.position - ball.previous_position) * alpha;
+            local.translation = [position.x, position.y, 0.0];
+            local.scale = [ball.radius, ball.radius, 1.0];
+        }
+    }
+}
 
-        // update ball position
-        // do ball/plank collision testing
-        for (ball, mut local) in (&mut balls, &mut locals).join() {
+/// Advances the planks and the ball by one physics step of `dt` seconds. Given the same
+/// state, step length and inputs this always plays out the same.
+pub fn step(balls : &mut WriteStorage<Ball>,
+            planks : &mut WriteStorage<Plank>,
+            field : &Field,
+            dt : f32,
+            serve : bool,
+            rules : &RulesConfig,
+            game_state : &mut GameState) {
+    game_state.ticks += 1;
 
-            // if round started, we randomize a velocity for the ball
-            if start_ball {
-                let x = if rand::random::<bool>() { 1. } else { -1. };
-                let y = rand::thread_rng().gen_range::<f32>(-1., 1.);
-                let v = BALL_VELOCITY;
-                ball.velocity = Vector2::new(x, y).normalize() * v;
-            }
+    let mut plank_collision_data : Vec<PlankCollisionData> = Vec::default();
+    // update plank positions
+    // do plank/boundary collision testing
+    for plank in planks.join() {
+        let start_position = plank.position;
+        plank.previous_position = start_position;
 
-            // update position of ball
-            let start_position = ball.position.clone();
-            ball.position += ball.velocity * dt;
-            let end_position = ball.position;
-
-            // check for plank collisions, and calculate response
-            for cd in &plank_collision_data {
-                match cd.collision_test(&start_position, &end_position) {
-                    Some(result) => {
-                        ball.velocity.x = -ball.velocity.x;
-                        ball.velocity *= BALL_BOUNCE_VELOCITY_INCREASE;
-                        ball.position.x = result.new_x;
-                    },
-                    None => ()
-                };
+        // update plank position, pointer control moves no faster than the keys
+        match plank.control {
+            PaddleControl::Axis => {
+                plank.position += plank.velocity_up * dt;
+                plank.position -= plank.velocity_down * dt;
+            },
+            _ => {
+                if let Some(target) = plank.target {
+                    plank.position += clamp_step(target - plank.position, PLANK_VELOCITY * dt);
+                }
             }
+        };
 
-            // check for boundary collision at top/bottom (should bounce)
-            if ball.position.y + ball.radius >= top_bound {
-                ball.velocity.y = -ball.velocity.y;
-                ball.position.y -= (ball.position.y + ball.radius - top_bound) * 2.;
-            }
-            if ball.position.y - ball.radius <= bottom_bound {
-                ball.velocity.y = -ball.velocity.y;
-                ball.position.y -= (ball.position.y - ball.radius - bottom_bound) * 2.;
-            }
+        // do boundary collision testing and response
+        if (plank.position + plank.dimensions.y/2.) >= field.top {
+            plank.position = field.top - plank.dimensions.y/2.;
+            plank.velocity_down = 0.;
+            plank.velocity_up = 0.;
+        }
 
-            // check for boundary collision at left/right (ends round and assigns points to victor)
-            if ball.position.x < left_bound {
-                ball.position = Point2::new(0., 0.);
-                ball.velocity = Vector2::new(0., 0.);
-
-                game_state.round += 1;
-                game_state.round_active = false;
-                game_state.right_score += 1;
-                println!("Left player missed the ball! Score is {} - {}",
-                         game_state.left_score,
-                         game_state.right_score);
-            }
-            if ball.position.x > right_bound {
-                ball.position = Point2::new(0., 0.);
-                ball.velocity = Vector2::new(0., 0.);
-
-                game_state.round += 1;
-                game_state.round_active = false;
-                game_state.left_score += 1;
-                println!("Right player missed the ball! Score is {} - {}",
-                         game_state.left_score,
-                         game_state.right_score);
-            }
+        if (plank.position - plank.dimensions.y/2.) <= field.bottom {
+            plank.position = field.bottom + plank.dimensions.y/2.;
+            plank.velocity_up = 0.;
+            plank.velocity_down = 0.;
+        }
 
-            // update transform
-            local.translation = [ball.position.x, ball.position.y, 0.0];
-            local.scale = [ball.radius, ball.radius, 1.0];
+        // store plank collision data for ball collision testing, as the box the plank had at
+        // the start of the step and how far it moved
+        plank_collision_data.push(PlankCollisionData::new(plank.side.clone(),
+                                                          Point2::new(field.plank_x(plank), start_position),
+                                                          plank.dimensions / 2.,
+                                                          Vector2::new(0., plank.position - start_position)));
+    }
+
+    // update ball position
+    // do ball/plank collision testing
+    for ball in balls.join() {
+        ball.previous_position = ball.position;
+
+        // if round started, we randomize a velocity for the ball
+        if serve {
+            let x = if game_state.rng.gen::<bool>() { 1. } else { -1. };
+            let y = game_state.rng.gen_range::<f32>(-1., 1.);
+            let v = if game_state.power_serve {
+                BALL_VELOCITY * POWER_SERVE_VELOCITY_INCREASE
+            } else {
+                BALL_VELOCITY
+            };
+            ball.velocity = Vector2::new(x, y).normalize() * v;
+            game_state.power_serve = false;
+        }
+
+        // move the ball, bouncing off the planks and the top and bottom of the field
+        let walls = [Wall::new(Point2::new(0., field.top), Vector2::new(0., -1.)),
+                     Wall::new(Point2::new(0., field.bottom), Vector2::new(0., 1.))];
+        // each plank speeds the ball up once a tick, however often the ball touches it
+        let mut boosted : Vec<usize> = Vec::new();
+        let respond = |contact : &Contact, velocity : Vector2<f32>| match contact.surface {
+            Surface::Plank(index) => {
+                let velocity = bounce_off_plank(contact, velocity, rules);
+                if boosted.contains(&index) {
+                    velocity
+                } else {
+                    boosted.push(index);
+                    velocity * BALL_BOUNCE_VELOCITY_INCREASE
+                }
+            },
+            Surface::Wall(_) => reflect(velocity, contact.normal)
+        };
+        let (position, velocity) = move_ball(ball.position,
+                                             ball.velocity,
+                                             ball.radius,
+                                             dt,
+                                             &plank_collision_data,
+                                             &walls,
+                                             respond);
+        ball.position = position;
+        ball.velocity = velocity;
+
+        // check for boundary collision at left/right (ends round and assigns points to victor)
+        if ball.position.x < field.left {
+            ball.position = Point2::new(0., 0.);
+            ball.previous_position = ball.position;
+            ball.velocity = Vector2::new(0., 0.);
+
+            game_state.round += 1;
+            game_state.round_active = false;
+            game_state.right_score += 1;
+            println!("Left player missed the ball! Score is {} - {}",
+                     game_state.left_score,
+                     game_state.right_score);
         }
+        if ball.position.x > field.right {
+            ball.position = Point2::new(0., 0.);
+            ball.previous_position = ball.position;
+            ball.velocity = Vector2::new(0., 0.);
 
+            game_state.round += 1;
+            game_state.round_active = false;
+            game_state.left_score += 1;
+            println!("Right player missed the ball! Score is {} - {}",
+                     game_state.left_score,
+                     game_state.right_score);
+        }
     }
 }
 
 fn update_velocity(planks : &mut WriteStorage<Plank>,
-                   side : Side,
-                   direction : Direction,
-                   state : StateAction) {
-    let new_velocity = match state {
-        StateAction::Activated | StateAction::Active => PLANK_VELOCITY,
-        StateAction::Deactivated => 0.0,
-    };
+                   player : usize,
+                   value : f32) {
     for plank in (planks).join() {
-        if plank.side == side {
-            match direction {
-                Direction::Up => plank.velocity_up = new_velocity,
-                Direction::Down => plank.velocity_down = new_velocity
-            }
+        if plank.player == player && plank.control == PaddleControl::Axis {
+            plank.velocity_up = value.max(0.0) * PLANK_VELOCITY;
+            plank.velocity_down = (-value).max(0.0) * PLANK_VELOCITY;
         }
     }
-}
\ No newline at end of file
+}
+
+/// Sets where each pointer controlled plank heads, for the physics steps to move it there no
+/// faster than the keys would. `motion` is the cursor motion since the last step, in window
+/// heights.
+fn update_targets(planks : &mut WriteStorage<Plank>,
+                  input : &InputState,
+                  camera : &Camera,
+                  field : &Field,
+                  motion : f64) {
+    for plank in planks.join() {
+        let target = match plank.control {
+            PaddleControl::Axis => None,
+            PaddleControl::Cursor => input.cursor_world(camera).map(|cursor| cursor.y),
+            PaddleControl::Motion => {
+                // motion has y pointing down
+                let step = -motion as f32 * (field.top - field.bottom);
+                Some(plank.target.unwrap_or(plank.position) + step)
+            },
+            PaddleControl::Touch(button) => {
+                input.touch_region(button)
+                    .and_then(|position| cursor_to_world(position, camera))
+                    .map(|touch| touch.y)
+            }
+        }.map(|target| field.clamp_plank(plank, target));
+        plank.target = target;
+    }
+}
+
+/// Sends the ball off a plank face at an angle set by where it hit, flat in the centre and up
+/// to the maximum angle at the ends, with some of the plank's movement added as spin. Hits on
+/// the top and bottom of a plank reflect relative to the plank, so a plank moving into the
+/// ball carries it along instead of closing on it again.
+fn bounce_off_plank(contact : &Contact,
+                    velocity : Vector2<f32>,
+                    rules : &RulesConfig) -> Vector2<f32> {
+    let plank_velocity = contact.velocity;
+    if contact.normal.x.abs() < 0.5 {
+        return reflect(velocity - plank_velocity, contact.normal) + plank_velocity;
+    }
+    let speed = velocity.magnitude();
+    let max_angle = rules.max_bounce_angle.to_radians();
+    let direction = contact.normal.x.signum();
+    let angle = contact.offset * max_angle;
+    let mut outgoing = Vector2::new(direction * angle.cos(), angle.sin()) * speed;
+    outgoing.y += rules.spin * plank_velocity.y;
+
+    // spin can steepen the angle, but not past the maximum
+    let angle = outgoing.y.atan2(outgoing.x.abs()).max(-max_angle).min(max_angle);
+    Vector2::new(direction * angle.cos(), angle.sin()) * speed
+}
+
+fn clamp_step(step : f32, max_step : f32) -> f32 {
+    step.max(-max_step).min(max_step)
+}
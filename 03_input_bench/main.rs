@@ -0,0 +1,71 @@
+extern crate amethyst;
+extern crate remawin;
+extern crate glutin;
+extern crate time;
+extern crate serde;
+extern crate amethyst_experiments;
+
+#[macro_use]
+extern crate serde_derive;
+
+use amethyst::{WindowEvent, Event, ElementState, MouseButton, VirtualKeyCode};
+use glutin::{MouseScrollDelta, TouchPhase};
+
+use amethyst_experiments::AmethystEventMapper;
+use input::{InputContext, Action};
+
+// the bench maps through the same actions and bindings as the hello example
+#[path = "../01_hello/input.rs"]
+mod input;
+
+const WINDOW_SIZE : (f64, f64) = (1024.0, 768.0);
+const FRAMES : usize = 1000;
+const BURSTS : [usize; 4] = [1, 10, 100, 1000];
+
+/// A mix of the window events a player produces: cursor movement, key and button presses,
+/// wheel steps and typed characters.
+fn synthetic_event(index : usize) -> WindowEvent {
+    let payload = match index % 8 {
+        0 => Event::KeyboardInput(ElementState::Pressed, 17, Some(VirtualKeyCode::W)),
+        1 => Event::KeyboardInput(ElementState::Released, 17, Some(VirtualKeyCode::W)),
+        2 => Event::MouseInput(ElementState::Pressed, MouseButton::Left),
+        3 => Event::MouseInput(ElementState::Released, MouseButton::Left),
+        4 => Event::MouseWheel(MouseScrollDelta::LineDelta(0.0, 1.0), TouchPhase::Moved),
+        5 => Event::ReceivedCharacter('w'),
+        _ => Event::MouseMoved((index % 1024) as i32, (index % 768) as i32)
+    };
+    WindowEvent {
+        payload : payload
+    }
+}
+
+fn main() {
+    let input_path = format!("{}/01_hello/resources/bindings.ron",
+                       env!("CARGO_MANIFEST_DIR"));
+    for &burst in BURSTS.iter() {
+        let mut event_mapper = AmethystEventMapper::<Action, InputContext>::new(WINDOW_SIZE);
+        if let Err(err) = event_mapper.load_bindings(&input_path) {
+            println!("Invalid input bindings:\n{}", err);
+            return;
+        }
+        event_mapper.remapper_mut()
+            .activate_context(&InputContext::Default, 1);
+        event_mapper.enable_metrics();
+
+        let frames : Vec<Vec<WindowEvent>> = (0..FRAMES)
+            .map(|frame| (0..burst).map(|i| synthetic_event(frame * burst + i)).collect())
+            .collect();
+        let started = time::precise_time_s();
+        let mut mapped = 0;
+        for events in frames.iter() {
+            mapped += event_mapper.process(events).len();
+        }
+        let elapsed = time::precise_time_s() - started;
+
+        println!("{} frames of {} window events: {:.3}s, {:.0} events/s, {} mapped events",
+                 FRAMES, burst, elapsed, (FRAMES * burst) as f64 / elapsed, mapped);
+        if let Some(metrics) = event_mapper.metrics() {
+            println!("{}\n", metrics);
+        }
+    }
+}
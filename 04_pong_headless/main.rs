@@ -0,0 +1,146 @@
+extern crate amethyst;
+extern crate remawin;
+extern crate cgmath;
+extern crate shrev;
+extern crate rand;
+extern crate serde;
+extern crate ron;
+extern crate amethyst_experiments;
+
+#[macro_use]
+extern crate serde_derive;
+
+use remawin::raw::{RawInput, RawInputEvent, RawInputAction, RawInputModifiers};
+use remawin::types::{DeviceType, KeyCode};
+
+use std::env;
+use std::process;
+
+use amethyst_experiments::recording::InputRecording;
+
+use config::{ControlConfig, RulesConfig};
+use headless::Headless;
+use input::init_input_system;
+
+// the simulation runs the pong example's world, systems and bindings
+#[path = "../02_pong/input.rs"]
+mod input;
+#[path = "../02_pong/config.rs"]
+mod config;
+#[path = "../02_pong/comp.rs"]
+mod comp;
+#[path = "../02_pong/event.rs"]
+mod event;
+#[path = "../02_pong/system.rs"]
+mod system;
+#[path = "../02_pong/setup.rs"]
+mod setup;
+#[path = "../02_pong/headless.rs"]
+#[allow(dead_code)]
+mod headless;
+
+const WINDOW_SIZE : (f64, f64) = (1024.0, 768.0);
+/// Ticks to wait after a round ends before serving again.
+const SERVE_DELAY : u64 = 60;
+
+fn space(t : f64, action : RawInputAction) -> RawInput {
+    RawInput::new(t, DeviceType::Keyboard, 0,
+                  RawInputEvent::Key(KeyCode::Space, action, RawInputModifiers::empty()))
+}
+
+/// Plays a match without a window and prints the score. Nobody moves the planks, and unless a
+/// recording is given the ball is served with the space key whenever a round is over.
+///
+/// Usage: pong_headless [--ticks N] [--replay recording.ron] [--expect LEFT:RIGHT]
+///
+/// With `--expect`, exits with an error if the final score differs.
+fn main() {
+    let resource = |name : &str| format!("{}/02_pong/resources/{}", env!("CARGO_MANIFEST_DIR"), name);
+
+    let mut ticks = 7200;
+    let mut replay = None;
+    let mut expect = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next();
+        match (arg.as_str(), value) {
+            ("--ticks", Some(value)) => ticks = value.parse().expect("--ticks takes a number of ticks"),
+            ("--replay", Some(value)) => replay = Some(value),
+            ("--expect", Some(value)) => {
+                let scores : Vec<u32> = value.split(':')
+                    .map(|score| score.parse().expect("--expect takes LEFT:RIGHT"))
+                    .collect();
+                if scores.len() != 2 {
+                    println!("--expect takes LEFT:RIGHT");
+                    process::exit(2);
+                }
+                expect = Some((scores[0], scores[1]));
+            },
+            (arg, _) => {
+                println!("Unknown or incomplete argument {}", arg);
+                process::exit(2);
+            }
+        };
+    }
+
+    let input = init_input_system(WINDOW_SIZE,
+                                  &resource("bindings.ron"),
+                                  &resource("bindings_player.ron"),
+                                  &resource("bindings_user.ron"),
+                                  &resource("combos.ron"),
+                                  &resource("axes.ron"),
+                                  &resource("axes_user.ron"),
+                                  &resource("touch_regions.ron"));
+    let rules = RulesConfig::load(&resource("rules.ron"));
+    let tick_length = rules.tick_length() as f64;
+    let mut headless = Headless::new(WINDOW_SIZE,
+                                     input,
+                                     ControlConfig::load(&resource("controls.ron")),
+                                     rules);
+
+    match replay {
+        Some(path) => {
+            match InputRecording::load(&path) {
+                Ok(recording) => {
+                    headless.replay(recording);
+                },
+                Err(err) => {
+                    println!("Failed loading recording {}: {:?}", path, err);
+                    process::exit(2);
+                }
+            };
+            headless.run(ticks);
+        },
+        None => {
+            let mut next_serve = 0;
+            while headless.ticks() < ticks {
+                let tick = headless.ticks();
+                if tick >= next_serve && !headless.game_state().round_active {
+                    headless
+                        .push_raw_input(tick, vec![space(tick as f64 * tick_length, RawInputAction::Press)])
+                        .push_raw_input(tick + 1, vec![space((tick + 1) as f64 * tick_length, RawInputAction::Release)]);
+                    next_serve = tick + SERVE_DELAY;
+                }
+                if !headless.tick() {
+                    break;
+                }
+            }
+        }
+    };
+
+    let score = {
+        let game_state = headless.game_state();
+        (game_state.left_score, game_state.right_score)
+    };
+    println!("After {} ticks ({:.1}s) the score is {} - {}",
+             headless.ticks(),
+             headless.ticks() as f64 * tick_length,
+             score.0,
+             score.1);
+    if let Some(expected) = expect {
+        if score != expected {
+            println!("Expected the score to be {} - {}", expected.0, expected.1);
+            process::exit(1);
+        }
+    }
+}
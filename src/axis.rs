@@ -0,0 +1,768 @@
+use remawin::{ActionMetadata, MappedType};
+use remawin::raw::{RawInput, RawInputEvent, RawInputAction};
+use remawin::types::KeyCode;
+use ron;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::cmp::Eq;
+use std::fmt::Debug;
+use std::clone::Clone;
+
+use bindings::{BindingsError, BindingsIssue, list_item_lines, parse_error};
+
+/// Where a virtual axis reads its input from.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum AxisSource {
+    /// Two digital keys, pushing the axis towards -1 and 1.
+    Keys {
+        negative : KeyCode,
+        positive : KeyCode
+    },
+    /// A gamepad axis, by the code used in `RawInputEvent::Axis`.
+    Stick(u32)
+}
+
+impl AxisSource {
+    /// Key pushing a key-driven axis in `direction`.
+    pub fn key(&self, direction : AxisDirection) -> Option<&KeyCode> {
+        match (self, direction) {
+            (&AxisSource::Keys { ref negative, .. }, AxisDirection::Negative) => Some(negative),
+            (&AxisSource::Keys { ref positive, .. }, AxisDirection::Positive) => Some(positive),
+            _ => None
+        }
+    }
+
+    fn set_key(&mut self, direction : AxisDirection, key : KeyCode) {
+        match (self, direction) {
+            (&mut AxisSource::Keys { ref mut negative, .. }, AxisDirection::Negative) => *negative = key,
+            (&mut AxisSource::Keys { ref mut positive, .. }, AxisDirection::Positive) => *positive = key,
+            _ => ()
+        };
+    }
+}
+
+/// One of the two keys of a key-driven axis.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AxisDirection {
+    Negative,
+    Positive
+}
+
+/// Tuning of a virtual axis.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct AxisSettings {
+    /// Stick deflection ignored around the centre, as a fraction of the full range.
+    pub dead_zone : f32,
+    /// Units per second a key-driven axis moves towards its target.
+    pub sensitivity : f32,
+    /// Units per second a key-driven axis falls back to 0 when no key is held.
+    pub gravity : f32,
+    /// Jump to 0 first when the opposite key is pressed.
+    pub snap : bool,
+    /// Response curve exponent applied to the final value, 1 is linear.
+    pub exponent : f32
+}
+
+impl Default for AxisSettings {
+    fn default() -> AxisSettings {
+        AxisSettings {
+            dead_zone : 0.2,
+            sensitivity : 3.0,
+            gravity : 3.0,
+            snap : true,
+            exponent : 1.0
+        }
+    }
+}
+
+/// A `Range` action in [-1, 1] driven by `source`. With a player, only input from devices
+/// assigned to that player moves the axis.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct VirtualAxis<ACTION> {
+    #[serde(default)]
+    pub player : Option<usize>,
+    pub action : ACTION,
+    pub source : AxisSource,
+    #[serde(default)]
+    pub settings : AxisSettings,
+    /// 1-based line the axis starts on in the axes file, when it came from there.
+    #[serde(skip)]
+    pub line : Option<usize>
+}
+
+/// A user change to one key of a key-driven axis, layered over the axes file. It applies to
+/// the first key-driven axis of `action` for `player`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AxisOverride<ACTION> {
+    #[serde(default)]
+    pub player : Option<usize>,
+    pub action : ACTION,
+    pub direction : AxisDirection,
+    pub key : KeyCode
+}
+
+/// Result of a completed axis rebind.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AxisRebindOutcome<ACTION> {
+    pub player : Option<usize>,
+    pub action : ACTION,
+    pub direction : AxisDirection,
+    pub key : KeyCode,
+    /// Axis key that used `key` before, and now has the previous key of `action` instead.
+    pub swapped : Option<(Option<usize>, ACTION, AxisDirection)>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AxisEvent<ACTION> {
+    pub action : ACTION,
+    pub player : Option<usize>,
+    pub value : f32
+}
+
+fn read_axes(path : &Path) -> Result<String, BindingsError> {
+    let mut data = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut data))
+        .map_err(|err| BindingsError::Io(path.to_path_buf(), err))?;
+    Ok(data)
+}
+
+/// Reads a list of virtual axes from a RON file.
+pub fn load_axes<ACTION, P>(path : P) -> Result<Vec<VirtualAxis<ACTION>>, BindingsError>
+    where ACTION: DeserializeOwned,
+          P: AsRef<Path> {
+    let path = path.as_ref();
+    parse_axes(path, &read_axes(path)?)
+}
+
+fn parse_axes<ACTION>(path : &Path, data : &str) -> Result<Vec<VirtualAxis<ACTION>>, BindingsError>
+    where ACTION: DeserializeOwned {
+    let mut axes : Vec<VirtualAxis<ACTION>> = ron::de::from_str(data)
+        .map_err(|err| parse_error(path, err))?;
+    for (axis, line) in axes.iter_mut().zip(list_item_lines(data)) {
+        axis.line = Some(line);
+    }
+    Ok(axes)
+}
+
+/// Reads the axes file at `path` with the user overrides at `overrides` layered over it, and
+/// checks the result for `players` player slots. The error lists every problem found.
+pub fn load_layered_axes<ACTION>(path : &Path,
+                                 overrides : Option<&Path>,
+                                 players : usize) -> Result<Vec<VirtualAxis<ACTION>>, BindingsError>
+    where ACTION: PartialEq + Debug + ActionMetadata + DeserializeOwned {
+    let mut axes = load_axes::<ACTION, _>(path)?;
+    if let Some(overrides) = overrides {
+        apply_axis_overrides(&mut axes, &load_axis_overrides::<ACTION>(overrides)?);
+    }
+    validate_axes(path, overrides, players, &axes)?;
+    Ok(axes)
+}
+
+/// Reads user axis overrides, a missing file meaning none.
+pub fn load_axis_overrides<ACTION>(path : &Path) -> Result<Vec<AxisOverride<ACTION>>, BindingsError>
+    where ACTION: DeserializeOwned {
+    if !path.exists() {
+        return Ok(Vec::default());
+    }
+    let data = read_axes(path)?;
+    ron::de::from_str(&data).map_err(|err| parse_error(path, err))
+}
+
+pub fn save_axis_overrides<ACTION>(path : &Path, overrides : &[AxisOverride<ACTION>]) -> Result<(), BindingsError>
+    where ACTION: Serialize {
+    let data = ron::ser::to_string(&overrides)
+        .map_err(|err| BindingsError::Parse(path.to_path_buf(), format!("{:?}", err)))?;
+    File::create(path)
+        .and_then(|mut file| file.write_all(data.as_bytes()))
+        .map_err(|err| BindingsError::Io(path.to_path_buf(), err))
+}
+
+pub fn apply_axis_overrides<ACTION>(axes : &mut Vec<VirtualAxis<ACTION>>, overrides : &[AxisOverride<ACTION>])
+    where ACTION: PartialEq {
+    for o in overrides {
+        let target = axes.iter_mut()
+            .find(|a| a.player == o.player && a.action == o.action && a.source.key(o.direction).is_some());
+        if let Some(axis) = target {
+            axis.source.set_key(o.direction, o.key.clone());
+        }
+    }
+}
+
+/// Binds `key` to one direction of the first key-driven axis of `action` for `player`. If
+/// another axis key already used it, the two are swapped; every player is checked, since
+/// players may share a keyboard. The updated overrides are validated and written to
+/// `overrides`; the axes file is not touched.
+pub fn rebind_axis<ACTION>(path : &Path,
+                           overrides : &Path,
+                           players : usize,
+                           player : Option<usize>,
+                           action : ACTION,
+                           direction : AxisDirection,
+                           key : KeyCode) -> Result<AxisRebindOutcome<ACTION>, BindingsError>
+    where ACTION: Clone + PartialEq + Debug + ActionMetadata + Serialize + DeserializeOwned {
+    let mut user = load_axis_overrides::<ACTION>(overrides)?;
+    let mut axes = load_axes::<ACTION, _>(path)?;
+    apply_axis_overrides(&mut axes, &user);
+
+    let target = match axes.iter().position(|a| a.player == player && a.action == action
+                                            && a.source.key(direction).is_some()) {
+        Some(target) => target,
+        None => return Err(BindingsError::NotConfigured(
+            format!("{:?} has no key-driven axis to rebind", action)))
+    };
+    let previous = axes[target].source.key(direction).cloned().expect("Target axis has keys");
+    let mut conflict = None;
+    for (index, axis) in axes.iter().enumerate() {
+        for &other in &[AxisDirection::Negative, AxisDirection::Positive] {
+            if (index, other) != (target, direction) && axis.source.key(other) == Some(&key) {
+                conflict = Some((axis.player, axis.action.clone(), other));
+            }
+        }
+    }
+
+    let mut updated = vec![AxisOverride {
+        player : player,
+        action : action.clone(),
+        direction : direction,
+        key : key.clone()
+    }];
+    if let Some((ref player, ref action, direction)) = conflict {
+        updated.push(AxisOverride {
+            player : *player,
+            action : action.clone(),
+            direction : direction,
+            key : previous
+        });
+    }
+    // the axes already carry the old overrides, which the updated ones replace
+    apply_axis_overrides(&mut axes, &updated);
+    user.retain(|o| !updated.iter().any(|u| u.player == o.player && u.action == o.action
+                                        && u.direction == o.direction));
+    user.extend(updated);
+
+    validate_axes(path, Some(overrides), players, &axes)?;
+    save_axis_overrides(overrides, &user)?;
+    Ok(AxisRebindOutcome {
+        player : player,
+        action : action,
+        direction : direction,
+        key : key,
+        swapped : conflict
+    })
+}
+
+fn validate_axes<ACTION>(path : &Path,
+                         overrides : Option<&Path>,
+                         players : usize,
+                         axes : &[VirtualAxis<ACTION>]) -> Result<(), BindingsError>
+    where ACTION: Debug + ActionMetadata {
+    let mut issues = Vec::default();
+    for (i, axis) in axes.iter().enumerate() {
+        let name = match axis.player {
+            Some(player) => format!("axis {:?} of player {}", axis.action, player),
+            None => format!("axis {:?}", axis.action)
+        };
+        let issue = |message : String| BindingsIssue {
+            path : match (axis.line, overrides) {
+                (None, Some(overrides)) => overrides.to_path_buf(),
+                _ => path.to_path_buf()
+            },
+            line : axis.line,
+            context : String::new(),
+            message : format!("{}: {}", name, message)
+        };
+        match axis.action.mapped_type() {
+            MappedType::Range => (),
+            _ => issues.push(issue(format!("{:?} is not a Range action", axis.action)))
+        };
+        if let Some(player) = axis.player {
+            if player >= players {
+                issues.push(issue(format!("there is no player {}", player)));
+            }
+        }
+        if let AxisSource::Keys { ref negative, ref positive } = axis.source {
+            if negative == positive {
+                issues.push(issue(format!("{:?} is used for both directions", negative)));
+            }
+            for other in &axes[..i] {
+                for key in &[negative, positive] {
+                    let shared = other.source.key(AxisDirection::Negative) == Some(key)
+                        || other.source.key(AxisDirection::Positive) == Some(key);
+                    if shared {
+                        issues.push(issue(format!("{:?} also drives {:?}", key, other.action)));
+                    }
+                }
+            }
+        }
+        let settings = &axis.settings;
+        if !(settings.dead_zone >= 0.0 && settings.dead_zone < 1.0) {
+            issues.push(issue("dead_zone must be at least 0 and below 1".to_string()));
+        }
+        if !(settings.sensitivity > 0.0 && settings.sensitivity.is_finite()) {
+            issues.push(issue("sensitivity must be above 0".to_string()));
+        }
+        if !(settings.gravity >= 0.0 && settings.gravity.is_finite()) {
+            issues.push(issue("gravity must be at least 0".to_string()));
+        }
+        if !(settings.exponent > 0.0 && settings.exponent.is_finite()) {
+            issues.push(issue("exponent must be above 0".to_string()));
+        }
+    }
+    if issues.len() > 0 {
+        Err(BindingsError::Invalid(issues))
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct AxisState {
+    negative_held : bool,
+    positive_held : bool,
+    stick : f32,
+    value : f32
+}
+
+/// Integrates virtual axes from raw input over time.
+#[derive(Debug, Clone)]
+pub struct AxisTracker<ACTION> {
+    axes : Vec<(VirtualAxis<ACTION>, AxisState)>,
+    outputs : Vec<(ACTION, Option<usize>, f32)>,
+    last_update : Option<f64>
+}
+
+impl <ACTION> AxisTracker<ACTION>
+    where ACTION: Eq + Clone + Debug {
+
+    pub fn new() -> AxisTracker<ACTION> {
+        AxisTracker {
+            axes : Vec::default(),
+            outputs : Vec::default(),
+            last_update : None
+        }
+    }
+
+    pub fn add(&mut self, axis : VirtualAxis<ACTION>) {
+        self.axes.push((axis, AxisState {
+            negative_held : false,
+            positive_held : false,
+            stick : 0.0,
+            value : 0.0
+        }));
+    }
+
+    pub fn clear(&mut self) {
+        self.axes.clear();
+        self.outputs.clear();
+    }
+
+    /// Replaces every axis. Axes that are unchanged keep their state, and actions no axis
+    /// drives anymore fall back to 0 on the next update.
+    pub fn replace(&mut self, axes : Vec<VirtualAxis<ACTION>>) {
+        let mut old = Vec::default();
+        old.extend(self.axes.drain(..));
+        for axis in axes {
+            let kept = old.iter()
+                .position(|&(ref a, _)| a.player == axis.player && a.action == axis.action && a.source == axis.source)
+                .map(|index| old.remove(index).1);
+            match kept {
+                Some(state) => self.axes.push((axis, state)),
+                None => self.add(axis)
+            };
+        }
+    }
+
+    /// Lets go of every key and centres every stick, for when releases will not arrive, like
+    /// after losing focus.
+    pub fn release_all(&mut self) {
+        for &mut (_, ref mut state) in self.axes.iter_mut() {
+            state.negative_held = false;
+            state.positive_held = false;
+            state.stick = 0.0;
+        }
+    }
+
+    /// Current value of `action` for `player`, 0 if it has not moved.
+    pub fn value(&self, action : &ACTION, player : Option<usize>) -> f32 {
+        self.outputs.iter()
+            .find(|&&(ref a, p, _)| a == action && p == player)
+            .map(|&(_, _, value)| value)
+            .unwrap_or(0.0)
+    }
+
+    /// Feeds raw input owned by `player`, or shared input when `player` is `None`.
+    /// Losing focus releases every axis, whoever it belongs to.
+    pub fn process(&mut self, raw_input : &[RawInput], player : Option<usize>) {
+        for &mut (ref axis, ref mut state) in self.axes.iter_mut() {
+            let owned = axis.player.is_none() || axis.player == player;
+            for raw in raw_input {
+                match (&axis.source, &raw.event) {
+                    (_, &RawInputEvent::Focus(false)) => {
+                        state.negative_held = false;
+                        state.positive_held = false;
+                        state.stick = 0.0;
+                    },
+                    _ if !owned => (),
+                    (&AxisSource::Keys { ref negative, ref positive },
+                     &RawInputEvent::Key(ref keycode, ref action, _)) => {
+                        let pressed = match *action {
+                            RawInputAction::Press => true,
+                            _ => false
+                        };
+                        if keycode == negative {
+                            state.negative_held = pressed;
+                        } else if keycode == positive {
+                            state.positive_held = pressed;
+                        }
+                    },
+                    (&AxisSource::Stick(code), &RawInputEvent::Axis(axis_code, value)) => {
+                        if code == axis_code {
+                            state.stick = value as f32;
+                        }
+                    },
+                    _ => ()
+                };
+            }
+        }
+    }
+
+    /// Advances every axis to `now`, by the time since the previous update, and returns the
+    /// actions whose combined value changed.
+    pub fn update(&mut self, now : f64) -> Vec<AxisEvent<ACTION>> {
+        let dt = match self.last_update {
+            Some(last) => (now - last).max(0.0) as f32,
+            None => 0.0
+        };
+        self.last_update = Some(now);
+        self.advance(dt)
+    }
+
+    /// Advances every axis by `dt` seconds, and returns the actions whose combined value
+    /// changed. Axes sharing an action and player are combined by taking the largest deflection.
+    pub fn advance(&mut self, dt : f32) -> Vec<AxisEvent<ACTION>> {
+        // actions without axes left fall back to 0
+        let mut combined : Vec<(ACTION, Option<usize>, f32)> = self.outputs.iter()
+            .map(|&(ref action, player, _)| (action.clone(), player, 0.0))
+            .collect();
+        for &mut (ref axis, ref mut state) in self.axes.iter_mut() {
+            let value = step(&axis.source, &axis.settings, state, dt);
+            match combined.iter_mut().find(|&&mut (ref a, p, _)| a == &axis.action && p == axis.player) {
+                Some(entry) => {
+                    if value.abs() > entry.2.abs() {
+                        entry.2 = value;
+                    }
+                },
+                None => combined.push((axis.action.clone(), axis.player, value))
+            };
+        }
+
+        let mut events = Vec::default();
+        for (action, player, value) in combined {
+            let previous = self.value(&action, player);
+            if value != previous {
+                events.push(AxisEvent {
+                    action : action.clone(),
+                    player : player,
+                    value : value
+                });
+            }
+            match self.outputs.iter_mut().find(|&&mut (ref a, p, _)| a == &action && p == player) {
+                Some(entry) => entry.2 = value,
+                None => self.outputs.push((action, player, value))
+            };
+        }
+        events
+    }
+}
+
+/// Moves one axis forward by `dt` seconds, and returns its output after the response curve.
+fn step(source : &AxisSource, settings : &AxisSettings, state : &mut AxisState, dt : f32) -> f32 {
+    match *source {
+        AxisSource::Keys { .. } => {
+            let target = match (state.negative_held, state.positive_held) {
+                (true, false) => -1.0,
+                (false, true) => 1.0,
+                _ => 0.0
+            };
+            if target == 0.0 {
+                state.value = approach(state.value, 0.0, settings.gravity * dt);
+            } else {
+                if settings.snap && state.value * target < 0.0 {
+                    state.value = 0.0;
+                }
+                state.value = approach(state.value, target, settings.sensitivity * dt);
+            }
+        },
+        AxisSource::Stick(_) => {
+            let deflection = state.stick.abs();
+            state.value = if deflection <= settings.dead_zone || settings.dead_zone >= 1.0 {
+                0.0
+            } else {
+                state.stick.signum() * ((deflection - settings.dead_zone) / (1.0 - settings.dead_zone)).min(1.0)
+            };
+        }
+    };
+    state.value.signum() * state.value.abs().powf(settings.exponent)
+}
+
+fn approach(value : f32, target : f32, max_delta : f32) -> f32 {
+    if (target - value).abs() <= max_delta {
+        target
+    } else if target > value {
+        value + max_delta
+    } else {
+        value - max_delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use remawin::ActionArgument;
+    use remawin::raw::RawInputModifiers;
+    use remawin::types::DeviceType;
+    use test_util::TempFile;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    enum TestAction {
+        Move,
+        Turn,
+        Fire
+    }
+
+    impl ActionMetadata for TestAction {
+        fn mapped_type(&self) -> MappedType {
+            match *self {
+                TestAction::Fire => MappedType::Action,
+                _ => MappedType::Range
+            }
+        }
+
+        fn args(&self) -> Vec<ActionArgument> {
+            Vec::default()
+        }
+    }
+
+    /// Two players moving on keys, and the first turning on a stick.
+    const TWO_PLAYERS : &str = "[
+    (player: Some(0), action: Move, source: Keys(negative: S, positive: W)),
+    (player: Some(1), action: Move, source: Keys(negative: Down, positive: Up)),
+    (player: Some(0), action: Turn, source: Stick(0)),
+]";
+
+    /// Keys of the key-driven axes, by player.
+    fn keys(axes : &[VirtualAxis<TestAction>]) -> Vec<(Option<usize>, Option<KeyCode>, Option<KeyCode>)> {
+        axes.iter()
+            .filter(|axis| axis.source.key(AxisDirection::Negative).is_some())
+            .map(|axis| (axis.player,
+                         axis.source.key(AxisDirection::Negative).cloned(),
+                         axis.source.key(AxisDirection::Positive).cloned()))
+            .collect()
+    }
+
+    #[test]
+    fn rebinding_to_another_players_key_swaps_them() {
+        let axes = TempFile::new("rebind_axis_swaps.ron", TWO_PLAYERS);
+        let overrides = TempFile::missing("rebind_axis_swaps_user.ron");
+        let outcome = rebind_axis(axes.as_path(), overrides.as_path(), 2, Some(0), TestAction::Move,
+                                  AxisDirection::Positive, KeyCode::Up)
+            .expect("Rebinding player 0 up to Up");
+        assert_eq!(outcome.swapped, Some((Some(1), TestAction::Move, AxisDirection::Positive)));
+
+        let loaded = load_layered_axes::<TestAction>(axes.as_path(), Some(overrides.as_path()), 2)
+            .expect("Rebound axes load");
+        assert_eq!(keys(&loaded), vec![(Some(0), Some(KeyCode::S), Some(KeyCode::Up)),
+                                       (Some(1), Some(KeyCode::Down), Some(KeyCode::W))]);
+    }
+
+    #[test]
+    fn rebinding_to_a_free_key_keeps_earlier_overrides() {
+        let axes = TempFile::new("rebind_axis_free.ron", TWO_PLAYERS);
+        let overrides = TempFile::missing("rebind_axis_free_user.ron");
+        rebind_axis(axes.as_path(), overrides.as_path(), 2, Some(0), TestAction::Move,
+                    AxisDirection::Positive, KeyCode::Up)
+            .expect("Rebinding player 0 up to Up");
+        let outcome = rebind_axis(axes.as_path(), overrides.as_path(), 2, Some(1), TestAction::Move,
+                                  AxisDirection::Negative, KeyCode::K)
+            .expect("Rebinding player 1 down to K");
+        assert_eq!(outcome.swapped, None);
+
+        let loaded = load_layered_axes::<TestAction>(axes.as_path(), Some(overrides.as_path()), 2)
+            .expect("Rebound axes load");
+        assert_eq!(keys(&loaded), vec![(Some(0), Some(KeyCode::S), Some(KeyCode::Up)),
+                                       (Some(1), Some(KeyCode::K), Some(KeyCode::W))]);
+    }
+
+    #[test]
+    fn axes_without_keys_cannot_be_rebound() {
+        let axes = TempFile::new("rebind_axis_stick.ron", TWO_PLAYERS);
+        let overrides = TempFile::missing("rebind_axis_stick_user.ron");
+        match rebind_axis(axes.as_path(), overrides.as_path(), 2, Some(0), TestAction::Turn,
+                          AxisDirection::Positive, KeyCode::D) {
+            Err(BindingsError::NotConfigured(_)) => (),
+            other => panic!("expected NotConfigured, got {:?}", other)
+        };
+        assert!(!overrides.as_path().exists());
+    }
+
+    fn axis(source : AxisSource, settings : AxisSettings) -> VirtualAxis<TestAction> {
+        VirtualAxis {
+            player : None,
+            action : TestAction::Move,
+            source : source,
+            settings : settings,
+            line : None
+        }
+    }
+
+    fn keys_axis(snap : bool) -> AxisTracker<TestAction> {
+        let mut tracker = AxisTracker::new();
+        tracker.add(axis(AxisSource::Keys { negative : KeyCode::S, positive : KeyCode::W }, AxisSettings {
+            sensitivity : 2.0,
+            gravity : 4.0,
+            snap : snap,
+            .. AxisSettings::default()
+        }));
+        tracker
+    }
+
+    fn stick_axis(exponent : f32) -> AxisTracker<TestAction> {
+        let mut tracker = AxisTracker::new();
+        tracker.add(axis(AxisSource::Stick(1), AxisSettings {
+            dead_zone : 0.25,
+            exponent : exponent,
+            .. AxisSettings::default()
+        }));
+        tracker
+    }
+
+    fn key(tracker : &mut AxisTracker<TestAction>, keycode : KeyCode, action : RawInputAction) {
+        let event = RawInputEvent::Key(keycode, action, RawInputModifiers::empty());
+        tracker.process(&[RawInput::new(0.0, DeviceType::Keyboard, 0, event)], None);
+    }
+
+    fn stick(tracker : &mut AxisTracker<TestAction>, value : f64) -> f32 {
+        tracker.process(&[RawInput::new(0.0, DeviceType::Gamepad, 0, RawInputEvent::Axis(1, value))], None);
+        tracker.advance(0.125);
+        tracker.value(&TestAction::Move, None)
+    }
+
+    /// Values after each step of `dt` seconds.
+    fn steps(tracker : &mut AxisTracker<TestAction>, dts : &[f32]) -> Vec<f32> {
+        dts.iter()
+            .map(|&dt| {
+                tracker.advance(dt);
+                tracker.value(&TestAction::Move, None)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keys_move_by_sensitivity_and_fall_back_by_gravity() {
+        let mut tracker = keys_axis(true);
+        key(&mut tracker, KeyCode::W, RawInputAction::Press);
+        let events = tracker.advance(0.125);
+        assert_eq!(events, vec![AxisEvent { action : TestAction::Move, player : None, value : 0.25 }]);
+        assert_eq!(steps(&mut tracker, &[0.25, 0.25]), vec![0.75, 1.0]);
+        assert!(tracker.advance(0.25).is_empty());
+
+        key(&mut tracker, KeyCode::W, RawInputAction::Release);
+        assert_eq!(steps(&mut tracker, &[0.125, 0.25]), vec![0.5, 0.0]);
+    }
+
+    #[test]
+    fn opposite_keys_held_together_fall_back() {
+        let mut tracker = keys_axis(true);
+        key(&mut tracker, KeyCode::W, RawInputAction::Press);
+        steps(&mut tracker, &[0.5]);
+        key(&mut tracker, KeyCode::S, RawInputAction::Press);
+        assert_eq!(steps(&mut tracker, &[0.125, 0.25]), vec![0.5, 0.0]);
+        // letting go of one moves towards the other
+        key(&mut tracker, KeyCode::W, RawInputAction::Release);
+        assert_eq!(steps(&mut tracker, &[0.125]), vec![-0.25]);
+    }
+
+    #[test]
+    fn reversing_snaps_to_zero_first() {
+        let mut snapping = keys_axis(true);
+        let mut smooth = keys_axis(false);
+        for tracker in &mut [&mut snapping, &mut smooth] {
+            key(tracker, KeyCode::W, RawInputAction::Press);
+            tracker.advance(0.5);
+            key(tracker, KeyCode::W, RawInputAction::Release);
+            key(tracker, KeyCode::S, RawInputAction::Press);
+        }
+        assert_eq!(steps(&mut snapping, &[0.125]), vec![-0.25]);
+        assert_eq!(steps(&mut smooth, &[0.125]), vec![0.75]);
+    }
+
+    #[test]
+    fn stick_values_inside_the_dead_zone_are_zero() {
+        let mut tracker = stick_axis(1.0);
+        assert_eq!(stick(&mut tracker, 0.25), 0.0);
+        assert_eq!(stick(&mut tracker, -0.125), 0.0);
+        // the rest of the range is rescaled to start at 0
+        assert_eq!(stick(&mut tracker, 0.625), 0.5);
+        assert_eq!(stick(&mut tracker, -1.0), -1.0);
+    }
+
+    #[test]
+    fn exponent_curves_the_response_and_keeps_the_sign() {
+        let mut tracker = stick_axis(2.0);
+        assert_eq!(stick(&mut tracker, 0.625), 0.25);
+        assert_eq!(stick(&mut tracker, -0.625), -0.25);
+        assert_eq!(stick(&mut tracker, 1.0), 1.0);
+    }
+
+    /// Every problem with the axes in `data` for two players, as (line, message).
+    fn issues(data : &str) -> Vec<(Option<usize>, String)> {
+        let path = Path::new("axes.ron");
+        let axes = parse_axes::<TestAction>(path, data).expect("Test axes parse");
+        match validate_axes(path, None, 2, &axes) {
+            Ok(()) => Vec::default(),
+            Err(BindingsError::Invalid(issues)) => issues.into_iter()
+                .map(|issue| (issue.line, issue.message))
+                .collect(),
+            Err(err) => panic!("expected a list of issues, got {:?}", err)
+        }
+    }
+
+    #[test]
+    fn valid_axes_have_no_issues() {
+        assert!(issues(TWO_PLAYERS).is_empty());
+    }
+
+    #[test]
+    fn axis_issues_are_located() {
+        let data = "[
+    (player: Some(0), action: Fire, source: Stick(0)),
+    (player: Some(2), action: Move, source: Keys(negative: S, positive: S)),
+    (action: Turn, source: Keys(negative: A, positive: S)),
+]";
+        assert_eq!(issues(data), vec![
+            (Some(2), "axis Fire of player 0: Fire is not a Range action".to_string()),
+            (Some(3), "axis Move of player 2: there is no player 2".to_string()),
+            (Some(3), "axis Move of player 2: S is used for both directions".to_string()),
+            (Some(4), "axis Turn: S also drives Move".to_string())]);
+    }
+
+    #[test]
+    fn settings_out_of_range_are_reported() {
+        let data = "[
+    (action: Move, source: Stick(0),
+     settings: (dead_zone: 1.0, sensitivity: 0.0, gravity: -1.0, exponent: 0.0)),
+]";
+        assert_eq!(issues(data), vec![
+            (Some(2), "axis Move: dead_zone must be at least 0 and below 1".to_string()),
+            (Some(2), "axis Move: sensitivity must be above 0".to_string()),
+            (Some(2), "axis Move: gravity must be at least 0".to_string()),
+            (Some(2), "axis Move: exponent must be above 0".to_string())]);
+    }
+}
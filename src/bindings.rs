@@ -0,0 +1,1116 @@
+use remawin::{Context, ActionMetadata, ActionArgument, MappedType};
+use remawin::raw::{RawInputAction, RawInputModifiers};
+use remawin::raw;
+use remawin::types::{RawType, KeyCode, DeviceType};
+use ron;
+use time;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use std::fs;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use std::hash::Hash;
+use std::cmp::Eq;
+use std::fmt::{self, Debug, Display};
+use std::clone::Clone;
+
+/// Minimum time between two checks of the watched bindings file, in seconds.
+const WATCH_INTERVAL : f64 = 0.5;
+
+#[derive(Debug)]
+pub enum BindingsError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, String),
+    Invalid(Vec<BindingsIssue>),
+    NotConfigured(String),
+    UnknownPlayer(usize)
+}
+
+impl Display for BindingsError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &BindingsError::Io(ref path, ref err) =>
+                write!(f, "{}: could not read bindings: {}", path.display(), err),
+            &BindingsError::Parse(ref path, ref err) =>
+                write!(f, "{}: could not parse bindings: {}", path.display(), err),
+            &BindingsError::Invalid(ref issues) => {
+                for issue in issues {
+                    writeln!(f, "{}", issue)?;
+                }
+                Ok(())
+            },
+            &BindingsError::NotConfigured(ref message) => write!(f, "{}", message),
+            &BindingsError::UnknownPlayer(player) => write!(f, "there is no player {}", player)
+        }
+    }
+}
+
+/// A problem with a single mapping in a bindings file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BindingsIssue {
+    /// File the problem is in, empty for settings that were not read from a file.
+    pub path : PathBuf,
+    /// 1-based line of the problem, when it could be located.
+    pub line : Option<usize>,
+    pub context : String,
+    pub message : String
+}
+
+impl Display for BindingsIssue {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: ", self.path.display(), line)?,
+            None if self.path.as_os_str().is_empty() => (),
+            None => write!(f, "{}: ", self.path.display())?
+        };
+        if self.context.len() > 0 {
+            write!(f, "context {}: ", self.context)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Reads a bindings file into its contexts, without touching any remapper. Nothing is returned
+/// unless the whole file parses and validates, so a broken file never leaves a remapper half
+/// updated.
+pub fn load_contexts<ACTION, ID, P>(path : P) -> Result<Vec<Context<ACTION, ID>>, BindingsError>
+    where ACTION: Hash + Eq + Clone + Debug + ActionMetadata + Serialize + DeserializeOwned,
+          ID: Hash + Eq + Clone + Debug + Serialize + DeserializeOwned,
+          P: AsRef<Path> {
+    load_layered_contexts(path.as_ref(), None)
+}
+
+/// Like `load_contexts`, with the user overrides at `overrides` layered over the defaults.
+/// A missing overrides file is the same as no overrides. Mappings that require or exclude
+/// modifiers are left out, since a plain remapper cannot check them; `BindingsReMapper` can.
+pub fn load_layered_contexts<ACTION, ID>(path : &Path,
+                                         overrides : Option<&Path>)
+    -> Result<Vec<Context<ACTION, ID>>, BindingsError>
+    where ACTION: Hash + Eq + Clone + Debug + ActionMetadata + Serialize + DeserializeOwned,
+          ID: Hash + Eq + Clone + Debug + Serialize + DeserializeOwned {
+    let mut contexts = load_layered_bindings::<ACTION, ID>(path, overrides)?;
+    for context in contexts.iter_mut() {
+        context.mappings.retain(|m| m.raw_args.modifier_filter().is_empty());
+    }
+    to_contexts(path, &contexts)
+}
+
+/// Checks every mapping in the bindings file at `path`, and returns all problems found.
+pub fn validate_bindings_file<ACTION, ID, P>(path : P) -> Result<(), BindingsError>
+    where ACTION: Hash + Eq + Clone + Debug + ActionMetadata + Serialize + DeserializeOwned,
+          ID: Hash + Eq + Clone + Debug + Serialize + DeserializeOwned,
+          P: AsRef<Path> {
+    load_layered_bindings::<ACTION, ID>(path.as_ref(), None).map(|_| ())
+}
+
+/// Parses and validates the defaults at `path` with `overrides` applied, as plain data.
+pub fn load_layered_bindings<ACTION, ID>(path : &Path,
+                                         overrides : Option<&Path>)
+    -> Result<Vec<ContextBindings<ACTION, ID>>, BindingsError>
+    where ACTION: Hash + Eq + Clone + Debug + ActionMetadata + Serialize + DeserializeOwned,
+          ID: Hash + Eq + Clone + Debug + Serialize + DeserializeOwned {
+    let user = match overrides {
+        Some(overrides) => load_overrides::<ACTION, ID>(overrides)?,
+        None => Vec::default()
+    };
+    layer_bindings(path, overrides, &user)
+}
+
+fn read_bindings(path : &Path) -> Result<String, BindingsError> {
+    let mut data = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut data))
+        .map_err(|err| BindingsError::Io(path.to_path_buf(), err))?;
+    Ok(data)
+}
+
+/// A context as written in a bindings file. The remapper's own `Context` is built from these
+/// once they have been layered and validated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "Context")]
+pub struct ContextBindings<ACTION, ID> {
+    pub id : ID,
+    pub mappings : Vec<Mapping<ACTION>>
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mapping<ACTION> {
+    pub raw_type : RawType,
+    pub raw_args : MappingArgs,
+    pub action : ACTION,
+    /// 1-based line the mapping starts on in the defaults file, when it came from there.
+    #[serde(skip)]
+    pub line : Option<usize>
+}
+
+impl <ACTION> Mapping<ACTION> where ACTION: PartialEq {
+    /// True if some input triggers both mappings. Arguments that are left out match anything,
+    /// so `keycode W` and `keycode W, action Press` share the press of W, and two modifier
+    /// filters overlap unless one requires a modifier the other excludes. A mapping without
+    /// modifiers gives way to one that requires some, so W and Ctrl+W do not share input.
+    pub fn same_input(&self, other : &Mapping<ACTION>) -> bool {
+        let (a_filter, b_filter) = (self.raw_args.modifier_filter(), other.raw_args.modifier_filter());
+        self.same_trigger(other)
+            && !a_filter.required.intersects(b_filter.excluded)
+            && !b_filter.required.intersects(a_filter.excluded)
+            && !(a_filter.is_empty() && !b_filter.required.is_empty())
+            && !(b_filter.is_empty() && !a_filter.required.is_empty())
+    }
+
+    /// Like `same_input`, ignoring modifiers.
+    pub fn same_trigger(&self, other : &Mapping<ACTION>) -> bool {
+        fn overlap<T : PartialEq>(a : &Option<T>, b : &Option<T>) -> bool {
+            match (a, b) {
+                (&Some(ref a), &Some(ref b)) => a == b,
+                _ => true
+            }
+        }
+        let (a, b) = (&self.raw_args, &other.raw_args);
+        self.raw_type == other.raw_type
+            && overlap(&a.action, &b.action)
+            && overlap(&a.keycode, &b.keycode)
+            && overlap(&a.button, &b.button)
+            && overlap(&a.device_type, &b.device_type)
+            && overlap(&a.device_id, &b.device_id)
+    }
+}
+
+// Unknown fields are rejected, so that a misspelled field is reported instead of ignored.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MappingArgs {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action : Option<RawInputAction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keycode : Option<KeyCode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub button : Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_type : Option<DeviceType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_id : Option<u32>,
+    /// Modifiers that must be held for a Key or Button mapping to trigger.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modifiers : Vec<Modifier>,
+    /// Modifiers that must not be held for a Key or Button mapping to trigger.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_modifiers : Vec<Modifier>
+}
+
+impl MappingArgs {
+    pub fn modifier_filter(&self) -> ModifierFilter {
+        ModifierFilter {
+            required : modifier_bits(&self.modifiers),
+            excluded : modifier_bits(&self.excluded_modifiers)
+        }
+    }
+
+    /// The same arguments without modifier requirements, as the remapper understands them.
+    pub fn without_modifiers(&self) -> MappingArgs {
+        MappingArgs {
+            modifiers : Vec::default(),
+            excluded_modifiers : Vec::default(),
+            .. self.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Modifier {
+    Shift,
+    Control,
+    Alt,
+    Super
+}
+
+impl Modifier {
+    pub fn bits(&self) -> RawInputModifiers {
+        match *self {
+            Modifier::Shift => raw::SHIFT,
+            Modifier::Control => raw::CONTROL,
+            Modifier::Alt => raw::ALT,
+            Modifier::Super => raw::SUPER
+        }
+    }
+
+    /// Every modifier set in `modifiers`.
+    pub fn from_bits(modifiers : RawInputModifiers) -> Vec<Modifier> {
+        [Modifier::Shift, Modifier::Control, Modifier::Alt, Modifier::Super].iter()
+            .filter(|m| modifiers.contains(m.bits()))
+            .cloned()
+            .collect()
+    }
+}
+
+fn modifier_bits(modifiers : &[Modifier]) -> RawInputModifiers {
+    modifiers.iter().fold(RawInputModifiers::empty(), |bits, m| bits | m.bits())
+}
+
+/// Modifiers a mapping requires and excludes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModifierFilter {
+    pub required : RawInputModifiers,
+    pub excluded : RawInputModifiers
+}
+
+impl ModifierFilter {
+    pub fn none() -> ModifierFilter {
+        ModifierFilter {
+            required : RawInputModifiers::empty(),
+            excluded : RawInputModifiers::empty()
+        }
+    }
+
+    /// True for mappings that trigger whatever modifiers are held.
+    pub fn is_empty(&self) -> bool {
+        self.required.is_empty() && self.excluded.is_empty()
+    }
+
+    pub fn accepts(&self, modifiers : RawInputModifiers) -> bool {
+        modifiers.contains(self.required) && !modifiers.intersects(self.excluded)
+    }
+}
+
+/// A user override, replacing the default mappings of `action` in `context` that have the same
+/// `raw_type`. All overrides for one action and raw type together make up its new mappings,
+/// and an override without `raw_args` leaves the action unbound for that raw type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BindingOverride<ACTION, ID> {
+    pub context : ID,
+    pub action : ACTION,
+    pub raw_type : RawType,
+    #[serde(default)]
+    pub raw_args : Option<MappingArgs>
+}
+
+fn parse_bindings<ACTION, ID>(path : &Path) -> Result<Vec<ContextBindings<ACTION, ID>>, BindingsError>
+    where ACTION: Hash + Eq + Clone + Debug + DeserializeOwned,
+          ID: Hash + Eq + Clone + Debug + DeserializeOwned {
+    let data = read_bindings(path)?;
+    parse_bindings_data(path, &data)
+}
+
+/// Parses the contents of the bindings file at `path`, noting the line each mapping is on.
+fn parse_bindings_data<ACTION, ID>(path : &Path, data : &str) -> Result<Vec<ContextBindings<ACTION, ID>>, BindingsError>
+    where ACTION: Hash + Eq + Clone + Debug + DeserializeOwned,
+          ID: Hash + Eq + Clone + Debug + DeserializeOwned {
+    let mut contexts : Vec<ContextBindings<ACTION, ID>> = match ron::de::from_str(data) {
+        Ok(contexts) => contexts,
+        Err(err) => return Err(BindingsError::Invalid(parse_issues::<ACTION, ID>(path, data, err)))
+    };
+    for (context, list) in contexts.iter_mut().zip(list_spans(data, Some("mappings"))) {
+        for (mapping, item) in context.mappings.iter_mut().zip(list.items) {
+            mapping.line = Some(item.line);
+        }
+    }
+    Ok(contexts)
+}
+
+/// Locates what failed to parse. Serde reports errors like an unknown key code without a
+/// position, so each mapping is parsed on its own to find the ones that fail.
+fn parse_issues<ACTION, ID>(path : &Path, data : &str, err : ron::de::Error) -> Vec<BindingsIssue>
+    where ACTION: DeserializeOwned,
+          ID: Debug + DeserializeOwned {
+    let contexts = list_spans(data, None).into_iter().next().map(|list| list.items).unwrap_or_default();
+    let mut issues = Vec::default();
+    for list in list_spans(data, Some("mappings")) {
+        let context = contexts.iter()
+            .position(|c| c.start <= list.span.start && list.span.end <= c.end)
+            .map(|index| {
+                // the id alone, with the mappings left out
+                let c = &contexts[index];
+                let header = format!("{}[]{}", &data[c.start..list.span.start], &data[list.span.end..c.end]);
+                match ron::de::from_str::<ContextBindings<IgnoredAction, ID>>(&header) {
+                    Ok(context) => format!("{:?}", context.id),
+                    Err(_) => format!("#{}", index + 1)
+                }
+            })
+            .unwrap_or_default();
+        for item in list.items {
+            if let Err(err) = ron::de::from_str::<Mapping<ACTION>>(&data[item.start..item.end]) {
+                issues.push(BindingsIssue {
+                    path : path.to_path_buf(),
+                    line : Some(item.line + error_line(&err).map(|line| line - 1).unwrap_or(0)),
+                    context : context.clone(),
+                    message : format!("{}", err)
+                });
+            }
+        }
+    }
+    if issues.len() == 0 {
+        issues.push(BindingsIssue {
+            path : path.to_path_buf(),
+            line : error_line(&err),
+            context : String::new(),
+            message : format!("{}", err)
+        });
+    }
+    issues
+}
+
+/// Stands in for the action type when only the id of a context is parsed.
+#[derive(Deserialize)]
+struct IgnoredAction;
+
+/// Error for a file that failed to parse, with the line ron found the problem on, if any.
+pub fn parse_error(path : &Path, err : ron::de::Error) -> BindingsError {
+    match error_line(&err) {
+        Some(line) => BindingsError::Parse(path.to_path_buf(), format!("line {}: {}", line, err)),
+        None => BindingsError::Parse(path.to_path_buf(), format!("{}", err))
+    }
+}
+
+/// 1-based line of a parse error, if ron knows where it happened.
+fn error_line(err : &ron::de::Error) -> Option<usize> {
+    match *err {
+        ron::de::Error::Parser(_, ref position) => Some(position.line),
+        _ => None
+    }
+}
+
+pub fn load_overrides<ACTION, ID>(path : &Path) -> Result<Vec<BindingOverride<ACTION, ID>>, BindingsError>
+    where ACTION: DeserializeOwned,
+          ID: DeserializeOwned {
+    if !path.exists() {
+        return Ok(Vec::default());
+    }
+    let data = read_bindings(path)?;
+    ron::de::from_str(&data).map_err(|err| parse_error(path, err))
+}
+
+pub fn save_overrides<ACTION, ID>(path : &Path,
+                                  overrides : &[BindingOverride<ACTION, ID>]) -> Result<(), BindingsError>
+    where ACTION: Serialize,
+          ID: Serialize {
+    let data = ron::ser::to_string(&overrides)
+        .map_err(|err| BindingsError::Parse(path.to_path_buf(), format!("{:?}", err)))?;
+    File::create(path)
+        .and_then(|mut file| file.write_all(data.as_bytes()))
+        .map_err(|err| BindingsError::Io(path.to_path_buf(), err))
+}
+
+pub fn apply_overrides<ACTION, ID>(contexts : &mut Vec<ContextBindings<ACTION, ID>>,
+                                   overrides : &[BindingOverride<ACTION, ID>])
+    where ACTION: Clone + PartialEq,
+          ID: Clone + PartialEq {
+    // first drop every default that is overridden, then add the overrides themselves
+    for o in overrides {
+        if let Some(context) = contexts.iter_mut().find(|c| c.id == o.context) {
+            context.mappings.retain(|m| !(m.action == o.action && m.raw_type == o.raw_type));
+        }
+    }
+    for o in overrides {
+        let mapping = match o.raw_args {
+            Some(ref raw_args) => Mapping {
+                raw_type : o.raw_type.clone(),
+                raw_args : raw_args.clone(),
+                action : o.action.clone(),
+                line : None
+            },
+            None => continue
+        };
+        match contexts.iter_mut().position(|c| c.id == o.context) {
+            Some(index) => contexts[index].mappings.push(mapping),
+            None => contexts.push(ContextBindings {
+                id : o.context.clone(),
+                mappings : vec![mapping]
+            })
+        };
+    }
+}
+
+/// Builds remapper contexts from validated bindings, by handing them to the remapper's own
+/// deserializer in the bindings file format. The remapper does not know about modifiers, so
+/// they are stripped; mappings that need them have to be split off first.
+pub fn to_contexts<ACTION, ID>(path : &Path,
+                               contexts : &[ContextBindings<ACTION, ID>])
+    -> Result<Vec<Context<ACTION, ID>>, BindingsError>
+    where ACTION: Hash + Eq + Clone + Serialize + DeserializeOwned,
+          ID: Hash + Eq + Clone + Debug + Serialize + DeserializeOwned {
+    let stripped : Vec<ContextBindings<ACTION, ID>> = contexts.iter()
+        .map(|context| ContextBindings {
+            id : context.id.clone(),
+            mappings : context.mappings.iter()
+                .map(|m| Mapping {
+                    raw_args : m.raw_args.without_modifiers(),
+                    .. m.clone()
+                })
+                .collect()
+        })
+        .collect();
+    let data = ron::ser::to_string(&stripped)
+        .map_err(|err| BindingsError::Parse(path.to_path_buf(), format!("{:?}", err)))?;
+    ron::de::from_str(&data)
+        .map_err(|err| BindingsError::Parse(path.to_path_buf(), format!("{:?}", err)))
+}
+
+/// Result of a completed rebind.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RebindOutcome<ACTION> {
+    pub action : ACTION,
+    pub mapping : Mapping<ACTION>,
+    /// Action that was bound to the captured input before, and now has the previous input of
+    /// `action` instead.
+    pub swapped : Option<ACTION>
+}
+
+/// Binds `mapping` to its action in `context`, replacing the action's mappings of the same raw
+/// type. If another action in the context already used that input, the two are swapped. The
+/// updated overrides are validated and written to `overrides`; the defaults are not touched.
+pub fn rebind<ACTION, ID>(path : &Path,
+                          overrides : &Path,
+                          context : &ID,
+                          mapping : Mapping<ACTION>) -> Result<RebindOutcome<ACTION>, BindingsError>
+    where ACTION: Hash + Eq + Clone + Debug + ActionMetadata + Serialize + DeserializeOwned,
+          ID: Hash + Eq + Clone + Debug + Serialize + DeserializeOwned {
+    let mut user = load_overrides::<ACTION, ID>(overrides)?;
+    let mut contexts = parse_bindings::<ACTION, ID>(path)?;
+    apply_overrides(&mut contexts, &user);
+    let current = match contexts.iter().find(|c| &c.id == context) {
+        Some(c) => c.mappings.clone(),
+        None => Vec::default()
+    };
+
+    let previous = current.iter()
+        .find(|m| m.action == mapping.action && m.raw_type == mapping.raw_type)
+        .cloned();
+    let conflict = current.iter()
+        .find(|m| m.action != mapping.action && m.same_input(&mapping))
+        .cloned();
+
+    let mut changed = vec![(mapping.action.clone(), mapping.raw_type.clone())];
+    let mut updated = vec![BindingOverride {
+        context : context.clone(),
+        action : mapping.action.clone(),
+        raw_type : mapping.raw_type.clone(),
+        raw_args : Some(mapping.raw_args.clone())
+    }];
+    if let Some(ref conflict) = conflict {
+        changed.push((conflict.action.clone(), conflict.raw_type.clone()));
+        let mut kept : Vec<MappingArgs> = current.iter()
+            .filter(|m| m.action == conflict.action && m.raw_type == conflict.raw_type)
+            .filter(|m| !m.same_input(&mapping))
+            .map(|m| m.raw_args.clone())
+            .collect();
+        if let Some(ref previous) = previous {
+            kept.push(previous.raw_args.clone());
+        }
+        if kept.len() == 0 {
+            // left without input of this type, which has to be recorded as well or the
+            // defaults would come back on the next load
+            updated.push(BindingOverride {
+                context : context.clone(),
+                action : conflict.action.clone(),
+                raw_type : conflict.raw_type.clone(),
+                raw_args : None
+            });
+        }
+        for raw_args in kept {
+            updated.push(BindingOverride {
+                context : context.clone(),
+                action : conflict.action.clone(),
+                raw_type : conflict.raw_type.clone(),
+                raw_args : Some(raw_args)
+            });
+        }
+    }
+
+    user.retain(|o| !(&o.context == context
+        && changed.iter().any(|&(ref a, ref t)| &o.action == a && &o.raw_type == t)));
+    user.extend(updated);
+
+    layer_bindings(path, Some(overrides), &user)?;
+    save_overrides(overrides, &user)?;
+    Ok(RebindOutcome {
+        action : mapping.action.clone(),
+        swapped : conflict.map(|c| c.action),
+        mapping : mapping
+    })
+}
+
+fn layer_bindings<ACTION, ID>(path : &Path,
+                              overrides_path : Option<&Path>,
+                              overrides : &[BindingOverride<ACTION, ID>])
+    -> Result<Vec<ContextBindings<ACTION, ID>>, BindingsError>
+    where ACTION: Hash + Eq + Clone + Debug + ActionMetadata + DeserializeOwned,
+          ID: Hash + Eq + Clone + Debug + DeserializeOwned {
+    let mut contexts = parse_bindings::<ACTION, ID>(path)?;
+    apply_overrides(&mut contexts, overrides);
+    validate_bindings(path, overrides_path, &contexts)?;
+    Ok(contexts)
+}
+
+fn validate_bindings<ACTION, ID>(path : &Path,
+                                 overrides : Option<&Path>,
+                                 contexts : &[ContextBindings<ACTION, ID>]) -> Result<(), BindingsError>
+    where ACTION: Hash + Eq + Clone + Debug + ActionMetadata,
+          ID: Hash + Eq + Clone + Debug {
+    let mut issues = Vec::default();
+    for context in contexts {
+        let context_name = format!("{:?}", context.id);
+        for (i, mapping) in context.mappings.iter().enumerate() {
+            let issue = |message : String| BindingsIssue {
+                path : match (mapping.line, overrides) {
+                    (None, Some(overrides)) => overrides.to_path_buf(),
+                    _ => path.to_path_buf()
+                },
+                line : mapping.line,
+                context : context_name.clone(),
+                message : message
+            };
+            for message in check_mapping(mapping) {
+                issues.push(issue(message));
+            }
+            for other in &context.mappings[..i] {
+                if other.same_input(mapping) {
+                    if other.action == mapping.action {
+                        issues.push(issue(format!("duplicate mapping of {:?} to {:?}",
+                                                  mapping.raw_type, mapping.action)));
+                    } else {
+                        issues.push(issue(format!("{:?} {:?} is mapped to both {:?} and {:?}",
+                                                  mapping.raw_type, mapping.raw_args,
+                                                  other.action, mapping.action)));
+                    }
+                }
+            }
+        }
+    }
+    if issues.len() > 0 {
+        Err(BindingsError::Invalid(issues))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_mapping<ACTION>(mapping : &Mapping<ACTION>) -> Vec<String>
+    where ACTION: Debug + ActionMetadata {
+    let mut messages = Vec::default();
+    let args = &mapping.raw_args;
+    let digital = match mapping.raw_type {
+        RawType::Key => {
+            if args.keycode.is_none() {
+                messages.push("Key mapping has no keycode".to_string());
+            }
+            if args.button.is_some() {
+                messages.push("button has no effect on a Key mapping".to_string());
+            }
+            true
+        },
+        RawType::Button => {
+            if args.button.is_none() {
+                messages.push("Button mapping has no button".to_string());
+            }
+            if args.keycode.is_some() {
+                messages.push("keycode has no effect on a Button mapping".to_string());
+            }
+            true
+        },
+        _ => {
+            if args.keycode.is_some() || args.button.is_some() || args.action.is_some() {
+                messages.push(format!("{:?} mapping takes no keycode, button or action",
+                                      mapping.raw_type));
+            }
+            if !args.modifier_filter().is_empty() {
+                messages.push(format!("{:?} mapping takes no modifiers, only Key and Button \
+                                       mappings do", mapping.raw_type));
+            }
+            false
+        }
+    };
+    for modifier in &args.modifiers {
+        if args.excluded_modifiers.contains(modifier) {
+            messages.push(format!("{:?} is both required and excluded", modifier));
+        }
+    }
+    match (mapping.action.mapped_type(), digital) {
+        (MappedType::Range, true) =>
+            messages.push(format!("Range action {:?} cannot be bound to {:?}",
+                                  mapping.action, mapping.raw_type)),
+        (MappedType::Action, false) | (MappedType::State, false) =>
+            messages.push(format!("{:?} cannot be bound to {:?}, which only produces ranges",
+                                  mapping.action, mapping.raw_type)),
+        _ => ()
+    };
+    for argument in mapping.action.args() {
+        match argument {
+            ActionArgument::CursorPosition => match mapping.raw_type {
+                RawType::Button => (),
+                _ => messages.push(format!("{:?} needs a cursor position, only Button mappings \
+                                            provide one", mapping.action))
+            },
+            #[allow(unreachable_patterns)]
+            _ => ()
+        }
+    }
+    messages
+}
+
+/// 1-based lines the items of the top level list in a RON file start on.
+pub fn list_item_lines(data : &str) -> Vec<usize> {
+    list_spans(data, None).into_iter()
+        .next()
+        .map(|list| list.items.iter().map(|item| item.line).collect())
+        .unwrap_or_default()
+}
+
+/// Where a value is in a RON file, as byte offsets, with the 1-based line it starts on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Span {
+    start : usize,
+    end : usize,
+    line : usize
+}
+
+/// A list in a RON file, with the spans of its items.
+#[derive(Debug, Clone, PartialEq)]
+struct ListSpans {
+    span : Span,
+    items : Vec<Span>
+}
+
+struct OpenList {
+    start : usize,
+    line : usize,
+    items : Vec<Span>,
+    item : Option<(usize, usize)>
+}
+
+/// Finds every list that is the value of a field named `field`, or the top level list when
+/// `field` is `None`, in the order they end. Only brackets, strings, comments and field names
+/// are looked at, so this works on files that do not parse, whatever the layout.
+fn list_spans(data : &str, field : Option<&str>) -> Vec<ListSpans> {
+    let chars : Vec<(usize, char)> = data.char_indices().collect();
+    let mut lists = Vec::default();
+    // one entry per open bracket, holding the list if it is one of those looked for
+    let mut stack : Vec<Option<OpenList>> = Vec::default();
+    let mut line = 1;
+    let mut token : Option<String> = None;
+    let mut field_name : Option<String> = None;
+    let mut last_end = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let (offset, c) = chars[i];
+        let next = chars.get(i + 1).map(|&(_, c)| c);
+        if c.is_whitespace() {
+            if c == '\n' {
+                line += 1;
+            }
+            i += 1;
+            continue;
+        }
+        if c == '/' && (next == Some('/') || next == Some('*')) {
+            let block = next == Some('*');
+            i += 2;
+            while i < chars.len() {
+                let c = chars[i].1;
+                if block && c == '*' && chars.get(i + 1).map(|&(_, c)| c) == Some('/') {
+                    i += 2;
+                    break;
+                }
+                if c == '\n' {
+                    if !block {
+                        break;
+                    }
+                    line += 1;
+                }
+                i += 1;
+            }
+            continue;
+        }
+        let closing = c == ')' || c == ']' || c == '}';
+        if c != ',' && !closing {
+            if let Some(&mut Some(ref mut list)) = stack.last_mut() {
+                if list.item.is_none() {
+                    list.item = Some((offset, line));
+                }
+            }
+        }
+        match c {
+            '(' | '[' | '{' => {
+                let wanted = c == '[' && match field {
+                    Some(field) => field_name.as_ref().map(|f| f.as_str()) == Some(field),
+                    None => stack.is_empty()
+                };
+                stack.push(if wanted {
+                    Some(OpenList {
+                        start : offset,
+                        line : line,
+                        items : Vec::default(),
+                        item : None
+                    })
+                } else {
+                    None
+                });
+                field_name = None;
+                token = None;
+                last_end = offset + 1;
+                i += 1;
+            },
+            ')' | ']' | '}' => {
+                if let Some(Some(mut list)) = stack.pop() {
+                    if let Some((start, line)) = list.item.take() {
+                        list.items.push(Span { start : start, end : last_end, line : line });
+                    }
+                    lists.push(ListSpans {
+                        span : Span { start : list.start, end : offset + 1, line : list.line },
+                        items : list.items
+                    });
+                }
+                field_name = None;
+                token = None;
+                last_end = offset + 1;
+                i += 1;
+            },
+            ',' => {
+                if let Some(&mut Some(ref mut list)) = stack.last_mut() {
+                    if let Some((start, line)) = list.item.take() {
+                        list.items.push(Span { start : start, end : last_end, line : line });
+                    }
+                }
+                field_name = None;
+                token = None;
+                i += 1;
+            },
+            ':' => {
+                field_name = token.take();
+                i += 1;
+            },
+            '"' | '\'' => {
+                i += 1;
+                while i < chars.len() && chars[i].1 != c {
+                    if chars[i].1 == '\\' {
+                        i += 1;
+                    } else if chars[i].1 == '\n' {
+                        line += 1;
+                    }
+                    i += 1;
+                }
+                last_end = chars.get(i).map(|&(end, _)| end + 1).unwrap_or(data.len());
+                token = None;
+                i += 1;
+            },
+            _ => {
+                let mut read = String::new();
+                while i < chars.len() && (chars[i].1.is_alphanumeric() || "_.+-".contains(chars[i].1)) {
+                    read.push(chars[i].1);
+                    i += 1;
+                }
+                if read.len() == 0 {
+                    // anything else is a single character
+                    read.push(c);
+                    i += 1;
+                }
+                last_end = offset + read.len();
+                token = Some(read);
+            }
+        }
+    }
+    lists
+}
+
+/// Watches a bindings file for changes by polling its modification time.
+#[derive(Debug, Clone)]
+pub struct BindingsWatcher {
+    path : PathBuf,
+    modified : Option<SystemTime>,
+    last_check : f64
+}
+
+impl BindingsWatcher {
+    pub fn new<P : AsRef<Path>>(path : P) -> BindingsWatcher {
+        let path = path.as_ref().to_path_buf();
+        let modified = modified_time(&path);
+        BindingsWatcher {
+            path : path,
+            modified : modified,
+            last_check : time::precise_time_s()
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns true once for every change to the file since the last call.
+    pub fn poll(&mut self) -> bool {
+        let now = time::precise_time_s();
+        if now - self.last_check < WATCH_INTERVAL {
+            return false;
+        }
+        self.last_check = now;
+        let modified = modified_time(&self.path);
+        if modified.is_some() && modified != self.modified {
+            self.modified = modified;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn modified_time(path : &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::TempFile;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    enum TestAction {
+        Jump,
+        Duck,
+        Move,
+        Click
+    }
+
+    impl ActionMetadata for TestAction {
+        fn mapped_type(&self) -> MappedType {
+            match *self {
+                TestAction::Move => MappedType::Range,
+                _ => MappedType::Action
+            }
+        }
+
+        fn args(&self) -> Vec<ActionArgument> {
+            match *self {
+                TestAction::Click => vec![ActionArgument::CursorPosition],
+                _ => Vec::default()
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    enum TestContext {
+        Default,
+        Menu
+    }
+
+    /// Every problem with the bindings in `data`, as (line, context, message).
+    fn issues(data : &str) -> Vec<(Option<usize>, String, String)> {
+        let path = Path::new("bindings.ron");
+        let result = parse_bindings_data::<TestAction, TestContext>(path, data)
+            .and_then(|contexts| validate_bindings(path, None, &contexts));
+        match result {
+            Ok(()) => Vec::default(),
+            Err(BindingsError::Invalid(issues)) => issues.into_iter()
+                .map(|issue| {
+                    assert_eq!(issue.path, path);
+                    (issue.line, issue.context, issue.message)
+                })
+                .collect(),
+            Err(err) => panic!("expected a list of issues, got {:?}", err)
+        }
+    }
+
+    /// Two contexts, the first with two mappings over several lines.
+    const TWO_CONTEXTS : &str = "[
+    Context(
+        id: Default,
+        mappings: [
+            (raw_type: Key, raw_args: (keycode: Some(W)), action: Jump),
+            (raw_type: Key, raw_args: (keycode: Some(S)), action: Duck),
+        ]
+    ),
+    Context(
+        id: Menu,
+        mappings: [(raw_type: Key, raw_args: (keycode: Some(Escape)), action: Jump)]
+    ),
+]";
+
+    #[test]
+    fn list_spans_find_the_mappings_of_every_context() {
+        let lists = list_spans(TWO_CONTEXTS, Some("mappings"));
+        assert_eq!(lists.len(), 2);
+        let lines : Vec<Vec<usize>> = lists.iter()
+            .map(|list| list.items.iter().map(|item| item.line).collect())
+            .collect();
+        assert_eq!(lines, vec![vec![5, 6], vec![11]]);
+        let first = lists[0].items[0];
+        assert_eq!(&TWO_CONTEXTS[first.start..first.end],
+                   "(raw_type: Key, raw_args: (keycode: Some(W)), action: Jump)");
+        assert_eq!((lists[0].span.line, lists[1].span.line), (4, 11));
+        assert_eq!(list_item_lines(TWO_CONTEXTS), vec![2, 9]);
+    }
+
+    #[test]
+    fn list_spans_skip_brackets_in_strings_and_comments() {
+        let data = "[
+    // a comment with ] and [ in it
+    Context(
+        id: \"menu ] [\",
+        /* mappings: [ */
+        mappings: [
+            (raw_type: Key, raw_args: (keycode: Some(W)), action: Jump),
+        ],
+    ),
+]";
+        let lists = list_spans(data, Some("mappings"));
+        assert_eq!(lists.len(), 1);
+        assert_eq!(lists[0].items.iter().map(|item| item.line).collect::<Vec<_>>(), vec![7]);
+        assert_eq!(list_item_lines(data), vec![3]);
+
+        let contexts = parse_bindings_data::<TestAction, String>(Path::new("bindings.ron"), data)
+            .expect("Bindings with a string id parse");
+        assert_eq!(contexts[0].id, "menu ] [");
+        assert_eq!(contexts[0].mappings[0].line, Some(7));
+    }
+
+    #[test]
+    fn valid_bindings_have_no_issues_and_know_their_lines() {
+        assert!(issues(TWO_CONTEXTS).is_empty());
+        let contexts = parse_bindings_data::<TestAction, TestContext>(Path::new("bindings.ron"), TWO_CONTEXTS)
+            .expect("Bindings parse");
+        let lines : Vec<Option<usize>> = contexts.iter()
+            .flat_map(|context| context.mappings.iter().map(|m| m.line))
+            .collect();
+        assert_eq!(lines, vec![Some(5), Some(6), Some(11)]);
+    }
+
+    #[test]
+    fn misspelled_key_code_is_located_in_its_context() {
+        let data = TWO_CONTEXTS.replace("Some(Escape)", "Some(Escpae)");
+        let found = issues(&data);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].0, found[0].1.as_str()), (Some(11), "Menu"));
+        assert!(found[0].2.contains("Escpae"), "message {:?} does not name the key code", found[0].2);
+    }
+
+    #[test]
+    fn every_failing_mapping_is_reported() {
+        let data = TWO_CONTEXTS
+            .replace("Some(W)", "Some(Double)")
+            .replace("action: Duck", "action: Crouch");
+        let found : Vec<(Option<usize>, String)> = issues(&data).into_iter()
+            .map(|(line, context, _)| (line, context))
+            .collect();
+        assert_eq!(found, vec![(Some(5), "Default".to_string()), (Some(6), "Default".to_string())]);
+    }
+
+    #[test]
+    fn syntax_error_in_a_mapping_is_located() {
+        let data = TWO_CONTEXTS.replace("(raw_type: Key, raw_args: (keycode: Some(S))", "(raw_type: Key raw_args: (keycode: Some(S))");
+        let found = issues(&data);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].0, found[0].1.as_str()), (Some(6), "Default"));
+    }
+
+    /// Bindings with a Default context holding `mappings`, one per line from line 5.
+    fn context(mappings : &[&str]) -> String {
+        format!("[\n    Context(\n        id: Default,\n        mappings: [\n{}\n        ]\n    )\n]",
+                mappings.iter().map(|m| format!("            {},", m)).collect::<Vec<_>>().join("\n"))
+    }
+
+    #[test]
+    fn duplicate_mappings_are_reported_on_the_second() {
+        let found = issues(&context(&["(raw_type: Key, raw_args: (keycode: Some(W)), action: Jump)",
+                                      "(raw_type: Key, raw_args: (keycode: Some(W), action: Some(Press)), action: Jump)"]));
+        assert_eq!(found, vec![(Some(6), "Default".to_string(), "duplicate mapping of Key to Jump".to_string())]);
+    }
+
+    #[test]
+    fn one_input_for_two_actions_is_a_conflict() {
+        let found = issues(&context(&["(raw_type: Key, raw_args: (keycode: Some(W)), action: Jump)",
+                                      "(raw_type: Key, raw_args: (keycode: Some(W)), action: Duck)"]));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, Some(6));
+        assert!(found[0].2.ends_with("is mapped to both Jump and Duck"), "unexpected message {:?}", found[0].2);
+    }
+
+    #[test]
+    fn inputs_told_apart_do_not_conflict() {
+        assert!(issues(&context(&["(raw_type: Key, raw_args: (keycode: Some(W), modifiers: [Control]), action: Jump)",
+                                  "(raw_type: Key, raw_args: (keycode: Some(W), excluded_modifiers: [Control]), action: Duck)"]))
+                .is_empty());
+        assert!(issues(&context(&["(raw_type: Key, raw_args: (keycode: Some(W), action: Some(Press)), action: Jump)",
+                                  "(raw_type: Key, raw_args: (keycode: Some(W), action: Some(Release)), action: Duck)"]))
+                .is_empty());
+        // a mapping without modifiers gives way to one that requires them
+        assert!(issues(&context(&["(raw_type: Key, raw_args: (keycode: Some(W), modifiers: [Control]), action: Jump)",
+                                  "(raw_type: Key, raw_args: (keycode: Some(W)), action: Duck)"]))
+                .is_empty());
+        // but not to one that only excludes some
+        assert_eq!(issues(&context(&["(raw_type: Key, raw_args: (keycode: Some(W), excluded_modifiers: [Shift]), action: Jump)",
+                                     "(raw_type: Key, raw_args: (keycode: Some(W)), action: Duck)"])).len(),
+                   1);
+    }
+
+    #[test]
+    fn range_action_cannot_be_bound_to_a_key() {
+        let found = issues(&context(&["(raw_type: Key, raw_args: (keycode: Some(A)), action: Move)"]));
+        assert_eq!(found, vec![(Some(5), "Default".to_string(),
+                                "Range action Move cannot be bound to Key".to_string())]);
+    }
+
+    #[test]
+    fn cursor_position_needs_a_button_mapping() {
+        let found = issues(&context(&["(raw_type: Key, raw_args: (keycode: Some(A)), action: Click)"]));
+        assert_eq!(found, vec![(Some(5), "Default".to_string(),
+                                "Click needs a cursor position, only Button mappings provide one".to_string())]);
+        assert!(issues(&context(&["(raw_type: Button, raw_args: (button: Some(1)), action: Click)"])).is_empty());
+    }
+
+    /// Key each action of `context` is bound to, in mapping order.
+    fn keys(contexts : &[ContextBindings<TestAction, TestContext>],
+            context : TestContext) -> Vec<(TestAction, Option<KeyCode>)> {
+        contexts.iter()
+            .filter(|c| c.id == context)
+            .flat_map(|c| c.mappings.iter().map(|m| (m.action.clone(), m.raw_args.keycode.clone())))
+            .collect()
+    }
+
+    fn key_mapping(action : TestAction, keycode : KeyCode) -> Mapping<TestAction> {
+        let mut raw_args = MappingArgs::default();
+        raw_args.keycode = Some(keycode);
+        Mapping {
+            raw_type : RawType::Key,
+            raw_args : raw_args,
+            action : action,
+            line : None
+        }
+    }
+
+    #[test]
+    fn rebinding_to_a_used_input_swaps_the_actions() {
+        let bindings = TempFile::new("rebind_swaps.ron", TWO_CONTEXTS);
+        let overrides = TempFile::missing("rebind_swaps_user.ron");
+        let outcome = rebind(bindings.as_path(), overrides.as_path(), &TestContext::Default,
+                             key_mapping(TestAction::Jump, KeyCode::S))
+            .expect("Rebinding Jump to S");
+        assert_eq!(outcome.swapped, Some(TestAction::Duck));
+
+        let contexts = load_layered_bindings::<TestAction, TestContext>(bindings.as_path(),
+                                                                        Some(overrides.as_path()))
+            .expect("Rebound bindings load");
+        assert_eq!(keys(&contexts, TestContext::Default),
+                   vec![(TestAction::Jump, Some(KeyCode::S)), (TestAction::Duck, Some(KeyCode::W))]);
+        assert_eq!(keys(&contexts, TestContext::Menu), vec![(TestAction::Jump, Some(KeyCode::Escape))]);
+        // the defaults are left as they were
+        assert_eq!(read_bindings(bindings.as_path()).expect("Reading the defaults"), TWO_CONTEXTS);
+    }
+
+    #[test]
+    fn rebinding_to_a_free_input_keeps_earlier_overrides() {
+        let bindings = TempFile::new("rebind_free.ron", TWO_CONTEXTS);
+        let overrides = TempFile::missing("rebind_free_user.ron");
+        rebind(bindings.as_path(), overrides.as_path(), &TestContext::Default,
+               key_mapping(TestAction::Jump, KeyCode::S))
+            .expect("Rebinding Jump to S");
+        let outcome = rebind(bindings.as_path(), overrides.as_path(), &TestContext::Default,
+                             key_mapping(TestAction::Duck, KeyCode::C))
+            .expect("Rebinding Duck to C");
+        assert_eq!(outcome.swapped, None);
+
+        let contexts = load_layered_bindings::<TestAction, TestContext>(bindings.as_path(),
+                                                                        Some(overrides.as_path()))
+            .expect("Rebound bindings load");
+        assert_eq!(keys(&contexts, TestContext::Default),
+                   vec![(TestAction::Jump, Some(KeyCode::S)), (TestAction::Duck, Some(KeyCode::C))]);
+    }
+}
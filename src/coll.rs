@@ -0,0 +1,568 @@
+use cgmath::{InnerSpace, Point2, Vector2};
+
+/// Below this a length or a motion component is treated as zero.
+const EPSILON : f32 = 1.0e-6;
+/// Most bounces handled in one step, the ball stops at the last contact after that.
+pub const MAX_BOUNCES : usize = 8;
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum Side {
+    Left,
+    Right
+}
+
+/// A plank as an axis aligned box, with where it was at the start of the step and how far it
+/// moved during it.
+#[derive(Debug)]
+pub struct PlankCollisionData {
+    side : Side,
+    center : Point2<f32>,
+    half_extents : Vector2<f32>,
+    displacement : Vector2<f32>
+}
+
+impl PlankCollisionData {
+
+    pub fn new(side : Side,
+               center : Point2<f32>,
+               half_extents : Vector2<f32>,
+               displacement : Vector2<f32>) -> PlankCollisionData {
+        PlankCollisionData {
+            side : side,
+            center : center,
+            half_extents : half_extents,
+            displacement : displacement
+        }
+    }
+
+    pub fn side(&self) -> &Side {
+        &self.side
+    }
+
+    /// How far the plank moves during the step.
+    pub fn displacement(&self) -> Vector2<f32> {
+        self.displacement
+    }
+
+    /// Sweeps a ball of `radius` from `start` to `end` against the plank as it moves over the
+    /// same step, and returns the first contact.
+    pub fn collision_test(&self,
+                          start : &Point2<f32>,
+                          end : &Point2<f32>,
+                          radius : f32) -> Option<PlankCollisionResult> {
+        self.collision_test_from(start, end, radius, 0.0)
+    }
+
+    /// Like `collision_test`, for a ball moving from `start` to `end` over the part of the
+    /// step after `from`, in [0, 1]. The time of the result is still a fraction of the step.
+    pub fn collision_test_from(&self,
+                               start : &Point2<f32>,
+                               end : &Point2<f32>,
+                               radius : f32,
+                               from : f32) -> Option<PlankCollisionResult> {
+        // seen from the plank, the plank stands still and the ball moves relative to it
+        let center = self.center + self.displacement * from;
+        let motion = (end - start) - self.displacement * (1.0 - from);
+        let min = center - self.half_extents;
+        let max = center + self.half_extents;
+        swept_circle_aabb(start, &motion, radius, &min, &max).map(|(t, normal)| {
+            let time = from + t * (1.0 - from);
+            let position = start + (end - start) * t;
+            let point = position - normal * radius;
+            let center = self.center + self.displacement * time;
+            let offset = ((point.y - center.y) / self.half_extents.y.max(EPSILON)).max(-1.0).min(1.0);
+            PlankCollisionResult::new(time, normal, point, position, offset)
+        })
+    }
+
+}
+
+/// First contact of a swept ball with a plank.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlankCollisionResult {
+    /// Fraction of the step's motion travelled before the contact, in [0, 1].
+    pub time : f32,
+    /// Unit normal of the plank surface at the contact, pointing towards the ball.
+    pub normal : Vector2<f32>,
+    /// Point where the ball touches the plank.
+    pub point : Point2<f32>,
+    /// Centre of the ball at the time of contact.
+    pub position : Point2<f32>,
+    /// Height of the contact point from the plank's centre, from -1 at its bottom to 1 at its top.
+    pub offset : f32
+}
+
+impl PlankCollisionResult {
+    pub fn new(time : f32,
+               normal : Vector2<f32>,
+               point : Point2<f32>,
+               position : Point2<f32>,
+               offset : f32) -> PlankCollisionResult {
+        PlankCollisionResult {
+            time : time,
+            normal : normal,
+            point : point,
+            position : position,
+            offset : offset
+        }
+    }
+}
+
+/// A straight boundary the ball bounces off, like the top and bottom of the field. The ball
+/// stays on the side `normal` points to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wall {
+    pub point : Point2<f32>,
+    pub normal : Vector2<f32>
+}
+
+impl Wall {
+    pub fn new(point : Point2<f32>, normal : Vector2<f32>) -> Wall {
+        Wall {
+            point : point,
+            normal : normal.normalize()
+        }
+    }
+
+    /// Fraction of the motion from `start` to `end` before a ball of `radius` touches the wall.
+    /// A ball already touching it only collides if it moves further in.
+    pub fn collision_test(&self, start : &Point2<f32>, end : &Point2<f32>, radius : f32) -> Option<f32> {
+        let before = (start - self.point).dot(self.normal) - radius;
+        let after = (end - self.point).dot(self.normal) - radius;
+        if after >= 0.0 || after >= before {
+            None
+        } else if before <= 0.0 {
+            Some(0.0)
+        } else {
+            Some(before / (before - after))
+        }
+    }
+}
+
+/// What the ball bounced off, by index into the planks or walls given to `move_ball`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Surface {
+    Plank(usize),
+    Wall(usize)
+}
+
+/// A bounce during `move_ball`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contact {
+    pub surface : Surface,
+    /// Fraction of the step passed at the contact, in [0, 1].
+    pub time : f32,
+    /// Unit normal of the surface at the contact, pointing towards the ball.
+    pub normal : Vector2<f32>,
+    /// Point where the ball touches the surface.
+    pub point : Point2<f32>,
+    /// Centre of the ball at the time of contact.
+    pub position : Point2<f32>,
+    /// For planks, height of the contact point from the plank's centre, from -1 at its bottom
+    /// to 1 at its top. Always 0 for walls.
+    pub offset : f32,
+    /// Velocity of the surface during the step, zero for walls.
+    pub velocity : Vector2<f32>
+}
+
+/// Mirrors `velocity` in the surface with unit `normal`.
+pub fn reflect(velocity : Vector2<f32>, normal : Vector2<f32>) -> Vector2<f32> {
+    velocity - normal * (2.0 * velocity.dot(normal))
+}
+
+/// Moves a ball of `radius` from `position` with `velocity` for `dt` seconds, bouncing off
+/// `planks` and `walls` as many times as happen within the step, up to `MAX_BOUNCES`. At every
+/// contact where the ball moves into the surface, relative to the surface's own velocity,
+/// `respond` gets the contact and the velocity before it, and returns the velocity after it;
+/// the rest of the step's travel continues along that velocity. The surface just hit is left
+/// out of the next sweep, so a ball leaving it is not caught on it again. Returns the final
+/// position and velocity.
+pub fn move_ball<F>(position : Point2<f32>,
+                    velocity : Vector2<f32>,
+                    radius : f32,
+                    dt : f32,
+                    planks : &[PlankCollisionData],
+                    walls : &[Wall],
+                    mut respond : F) -> (Point2<f32>, Vector2<f32>)
+    where F: FnMut(&Contact, Vector2<f32>) -> Vector2<f32> {
+    let mut position = position;
+    let mut velocity = velocity;
+    let mut from = 0.0f32;
+    let mut last : Option<Surface> = None;
+    for _ in 0..MAX_BOUNCES {
+        let end = position + velocity * (dt * (1.0 - from));
+        let contact = first_contact(&position, &end, radius, from, dt, planks, walls, last.as_ref());
+        match contact {
+            Some(contact) => {
+                if (velocity - contact.velocity).dot(contact.normal) < 0.0 {
+                    velocity = respond(&contact, velocity);
+                }
+                position = contact.position;
+                from = contact.time;
+                last = Some(contact.surface);
+            },
+            None => return (end, velocity)
+        };
+    }
+    (position, velocity)
+}
+
+/// Earliest contact of a ball moving from `start` to `end` over the part after `from` of a
+/// step of `dt` seconds, with any surface but `skip`.
+fn first_contact(start : &Point2<f32>,
+                 end : &Point2<f32>,
+                 radius : f32,
+                 from : f32,
+                 dt : f32,
+                 planks : &[PlankCollisionData],
+                 walls : &[Wall],
+                 skip : Option<&Surface>) -> Option<Contact> {
+    let mut first : Option<Contact> = None;
+    for (index, plank) in planks.iter().enumerate() {
+        if skip == Some(&Surface::Plank(index)) {
+            continue;
+        }
+        if let Some(hit) = plank.collision_test_from(start, end, radius, from) {
+            if first.as_ref().map(|f| hit.time < f.time).unwrap_or(true) {
+                first = Some(Contact {
+                    surface : Surface::Plank(index),
+                    time : hit.time,
+                    normal : hit.normal,
+                    point : hit.point,
+                    position : hit.position,
+                    offset : hit.offset,
+                    velocity : if dt > 0.0 { plank.displacement / dt } else { Vector2::new(0.0, 0.0) }
+                });
+            }
+        }
+    }
+    for (index, wall) in walls.iter().enumerate() {
+        if skip == Some(&Surface::Wall(index)) {
+            continue;
+        }
+        if let Some(t) = wall.collision_test(start, end, radius) {
+            let time = from + t * (1.0 - from);
+            if first.as_ref().map(|f| time < f.time).unwrap_or(true) {
+                let position = start + (end - start) * t;
+                first = Some(Contact {
+                    surface : Surface::Wall(index),
+                    time : time,
+                    normal : wall.normal,
+                    point : position - wall.normal * radius,
+                    position : position,
+                    offset : 0.0,
+                    velocity : Vector2::new(0.0, 0.0)
+                });
+            }
+        }
+    }
+    first
+}
+
+/// Sweeps a circle of `radius` from `start` along `motion` against the still box between `min`
+/// and `max`. Returns the fraction of `motion` travelled before first contact and the contact
+/// normal, pointing out of the box. Faces give axis normals and corners give normals pointing
+/// from the corner to the circle's centre. A circle already touching the box only collides if
+/// it moves further into it.
+pub fn swept_circle_aabb(start : &Point2<f32>,
+                         motion : &Vector2<f32>,
+                         radius : f32,
+                         min : &Point2<f32>,
+                         max : &Point2<f32>) -> Option<(f32, Vector2<f32>)> {
+    // overlapping at the start of the sweep
+    let closest = Point2::new(start.x.max(min.x).min(max.x), start.y.max(min.y).min(max.y));
+    let offset = start - closest;
+    if offset.magnitude2() < radius * radius {
+        let normal = if offset.magnitude2() > EPSILON * EPSILON {
+            offset.normalize()
+        } else {
+            inside_normal(start, min, max)
+        };
+        return if motion.dot(normal) < 0.0 { Some((0.0, normal)) } else { None };
+    }
+
+    // slab test against the box grown by the radius on every side
+    let mut enter = 0.0f32;
+    let mut exit = 1.0f32;
+    let mut face_normal = Vector2::new(0.0, 0.0);
+    let axes = [(start.x, motion.x, min.x, max.x, Vector2::new(1.0, 0.0)),
+                (start.y, motion.y, min.y, max.y, Vector2::new(0.0, 1.0))];
+    for &(s, d, lo, hi, axis) in axes.iter() {
+        let (lo, hi) = (lo - radius, hi + radius);
+        if d.abs() < EPSILON {
+            if s < lo || s > hi {
+                return None;
+            }
+        } else {
+            let (near, far, normal) = if d > 0.0 {
+                ((lo - s) / d, (hi - s) / d, -axis)
+            } else {
+                ((hi - s) / d, (lo - s) / d, axis)
+            };
+            if near > enter {
+                enter = near;
+                face_normal = normal;
+            }
+            exit = exit.min(far);
+            if enter > exit {
+                return None;
+            }
+        }
+    }
+
+    // a hit on the grown box within the span of a face is a hit on that face
+    let hit = start + motion * enter;
+    if face_normal.x != 0.0 && hit.y >= min.y && hit.y <= max.y {
+        return Some((enter, face_normal));
+    }
+    if face_normal.y != 0.0 && hit.x >= min.x && hit.x <= max.x {
+        return Some((enter, face_normal));
+    }
+
+    // otherwise it can only be the rounded corners
+    let corners = [Point2::new(min.x, min.y),
+                   Point2::new(min.x, max.y),
+                   Point2::new(max.x, min.y),
+                   Point2::new(max.x, max.y)];
+    let mut first : Option<(f32, Vector2<f32>)> = None;
+    for corner in corners.iter() {
+        if let Some(time) = sweep_circle_point(start, motion, radius, corner) {
+            if first.map(|(t, _)| time < t).unwrap_or(true) {
+                let normal = ((start + motion * time) - corner).normalize();
+                first = Some((time, normal));
+            }
+        }
+    }
+    first
+}
+
+/// Fraction of `motion` before a circle moving from `start` first touches `point`, if within it.
+fn sweep_circle_point(start : &Point2<f32>,
+                      motion : &Vector2<f32>,
+                      radius : f32,
+                      point : &Point2<f32>) -> Option<f32> {
+    let offset = start - point;
+    let a = motion.magnitude2();
+    if a < EPSILON * EPSILON {
+        return None;
+    }
+    let b = 2.0 * motion.dot(offset);
+    let c = offset.magnitude2() - radius * radius;
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let time = (-b - discriminant.sqrt()) / (2.0 * a);
+    if time >= 0.0 && time <= 1.0 {
+        Some(time)
+    } else {
+        None
+    }
+}
+
+/// Normal of the face nearest to a point inside the box.
+fn inside_normal(point : &Point2<f32>, min : &Point2<f32>, max : &Point2<f32>) -> Vector2<f32> {
+    let distances = [(point.x - min.x, Vector2::new(-1.0, 0.0)),
+                     (max.x - point.x, Vector2::new(1.0, 0.0)),
+                     (point.y - min.y, Vector2::new(0.0, -1.0)),
+                     (max.y - point.y, Vector2::new(0.0, 1.0))];
+    let mut nearest = distances[0];
+    for &(distance, normal) in distances.iter().skip(1) {
+        if distance < nearest.0 {
+            nearest = (distance, normal);
+        }
+    }
+    nearest.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RADIUS : f32 = 0.1;
+    const TOLERANCE : f32 = 1.0e-4;
+
+    /// Planks 0.1 wide and 0.6 high at x = -1 and x = 1, standing still.
+    fn planks() -> Vec<PlankCollisionData> {
+        vec![PlankCollisionData::new(Side::Left, Point2::new(-1.0, 0.0), Vector2::new(0.05, 0.3), Vector2::new(0.0, 0.0)),
+             PlankCollisionData::new(Side::Right, Point2::new(1.0, 0.0), Vector2::new(0.05, 0.3), Vector2::new(0.0, 0.0))]
+    }
+
+    /// Walls at y = 1 and y = -1, facing the centre.
+    fn walls() -> Vec<Wall> {
+        vec![Wall::new(Point2::new(0.0, 1.0), Vector2::new(0.0, -1.0)),
+             Wall::new(Point2::new(0.0, -1.0), Vector2::new(0.0, 1.0))]
+    }
+
+    /// Moves the ball with plain reflections off the surface, relative to its velocity, and
+    /// returns the final position and velocity with the contacts responded to.
+    fn bounce(position : (f32, f32),
+              velocity : (f32, f32),
+              dt : f32,
+              planks : &[PlankCollisionData],
+              walls : &[Wall]) -> (Point2<f32>, Vector2<f32>, Vec<Contact>) {
+        let mut contacts = Vec::new();
+        let (position, velocity) = move_ball(Point2::new(position.0, position.1),
+                                             Vector2::new(velocity.0, velocity.1),
+                                             RADIUS,
+                                             dt,
+                                             planks,
+                                             walls,
+                                             |contact, velocity| {
+                                                 contacts.push(contact.clone());
+                                                 reflect(velocity - contact.velocity, contact.normal) + contact.velocity
+                                             });
+        (position, velocity, contacts)
+    }
+
+    fn assert_close(actual : (Point2<f32>, Vector2<f32>), position : (f32, f32), velocity : (f32, f32)) {
+        assert!((actual.0.x - position.0).abs() < TOLERANCE && (actual.0.y - position.1).abs() < TOLERANCE,
+                "expected the ball at {:?}, it is at {:?}", position, actual.0);
+        assert!((actual.1.x - velocity.0).abs() < TOLERANCE && (actual.1.y - velocity.1).abs() < TOLERANCE,
+                "expected a velocity of {:?}, it is {:?}", velocity, actual.1);
+    }
+
+    #[test]
+    fn slow_ball_bounces_off_left_plank() {
+        // the face is at x = -0.95, so the ball touches it at x = -0.85 after 0.35 of its 0.5
+        // travel and goes back the remaining 0.15
+        let (position, velocity, contacts) = bounce((-0.5, 0.0), (-1.0, 0.0), 0.5, &planks(), &walls());
+        assert_close((position, velocity), (-0.7, 0.0), (1.0, 0.0));
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(contacts[0].surface, Surface::Plank(0));
+        assert!((contacts[0].time - 0.7).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn fast_ball_does_not_tunnel_through_left_plank() {
+        // travels 1.0 in one step, further than the plank is thick
+        let (position, velocity, contacts) = bounce((-0.5, 0.0), (-10.0, 0.0), 0.1, &planks(), &walls());
+        assert_close((position, velocity), (-0.2, 0.0), (10.0, 0.0));
+        assert_eq!(contacts.len(), 1);
+    }
+
+    #[test]
+    fn slow_ball_bounces_off_right_plank() {
+        let (position, velocity, contacts) = bounce((0.5, 0.0), (1.0, 0.0), 0.5, &planks(), &walls());
+        assert_close((position, velocity), (0.7, 0.0), (-1.0, 0.0));
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(contacts[0].surface, Surface::Plank(1));
+    }
+
+    #[test]
+    fn fast_ball_does_not_tunnel_through_right_plank() {
+        let (position, velocity, contacts) = bounce((0.5, 0.0), (10.0, 0.0), 0.1, &planks(), &walls());
+        assert_close((position, velocity), (0.2, 0.0), (-10.0, 0.0));
+        assert_eq!(contacts.len(), 1);
+    }
+
+    #[test]
+    fn slow_ball_bounces_off_top_wall() {
+        // touches at y = 0.9 after 0.4 of its 0.6 travel
+        let (position, velocity, contacts) = bounce((0.0, 0.5), (0.0, 1.0), 0.6, &planks(), &walls());
+        assert_close((position, velocity), (0.0, 0.7), (0.0, -1.0));
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(contacts[0].surface, Surface::Wall(0));
+    }
+
+    #[test]
+    fn fast_ball_does_not_tunnel_through_top_wall() {
+        // travels 2.0, 0.4 up to the wall and 1.6 back down, short of the bottom wall at -0.9
+        let (position, velocity, contacts) = bounce((0.0, 0.5), (0.0, 20.0), 0.1, &planks(), &walls());
+        assert_close((position, velocity), (0.0, -0.7), (0.0, -20.0));
+        assert_eq!(contacts.len(), 1);
+    }
+
+    #[test]
+    fn slow_ball_bounces_off_bottom_wall() {
+        let (position, velocity, contacts) = bounce((0.0, -0.5), (0.0, -1.0), 0.6, &planks(), &walls());
+        assert_close((position, velocity), (0.0, -0.7), (0.0, 1.0));
+        assert_eq!(contacts.len(), 1);
+        assert_eq!(contacts[0].surface, Surface::Wall(1));
+    }
+
+    #[test]
+    fn fast_ball_does_not_tunnel_through_bottom_wall() {
+        let (position, velocity, contacts) = bounce((0.0, -0.5), (0.0, -20.0), 0.1, &planks(), &walls());
+        assert_close((position, velocity), (0.0, 0.7), (0.0, 20.0));
+        assert_eq!(contacts.len(), 1);
+    }
+
+    #[test]
+    fn fast_ball_bounces_between_both_walls() {
+        // 0.4 up, 1.8 down and 0.4 back up
+        let (position, velocity, contacts) = bounce((0.0, 0.5), (0.0, 26.0), 0.1, &planks(), &walls());
+        assert_close((position, velocity), (0.0, -0.5), (0.0, 26.0));
+        let surfaces : Vec<Surface> = contacts.into_iter().map(|c| c.surface).collect();
+        assert_eq!(surfaces, vec![Surface::Wall(0), Surface::Wall(1)]);
+    }
+
+    #[test]
+    fn plank_moving_into_the_ball_pushes_it_along() {
+        // the plank rises at 1 under a ball rising at 0.2, 0.05 above its top: they meet after
+        // 0.0625s, and the ball leaves at 0.8 relative to the plank, so 1.8 in all
+        let plank = PlankCollisionData::new(Side::Left, Point2::new(-1.0, 0.0), Vector2::new(0.05, 0.3),
+                                            Vector2::new(0.0, 0.5));
+        let (position, velocity, contacts) = bounce((-1.0, 0.45), (0.0, 0.2), 0.5, &[plank], &[]);
+        assert_close((position, velocity), (-1.0, 0.4625 + 1.8 * 0.4375), (0.0, 1.8));
+        assert_eq!(contacts.len(), 1);
+        assert!((contacts[0].velocity.y - 1.0).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn ball_leaving_a_surface_is_not_bounced() {
+        // resting against the left plank's face and moving away from it
+        let (position, velocity, contacts) = bounce((-0.85, 0.0), (1.0, 0.0), 0.5, &planks(), &walls());
+        assert_close((position, velocity), (-0.35, 0.0), (1.0, 0.0));
+        assert!(contacts.is_empty());
+    }
+
+    #[test]
+    fn ball_hitting_a_corner_bounces_off_along_the_diagonal() {
+        // heads straight for the left plank's top right corner at (-0.95, 0.3), touching it after
+        // 0.3 of its 0.5 travel with the centre a radius out along the diagonal
+        let diagonal = RADIUS / 2.0f32.sqrt();
+        let contact = (-0.95 + diagonal, 0.3 + diagonal);
+        let (position, velocity, contacts) = bounce((contact.0 + 0.3, contact.1 + 0.3), (-1.0, -1.0), 0.5,
+                                                    &planks(), &walls());
+        assert_eq!(contacts.len(), 1);
+        assert!((contacts[0].time - 0.6).abs() < TOLERANCE);
+        assert!((contacts[0].normal.x - 0.5f32.sqrt()).abs() < TOLERANCE
+                && (contacts[0].normal.y - 0.5f32.sqrt()).abs() < TOLERANCE,
+                "expected a diagonal normal, got {:?}", contacts[0].normal);
+        assert!((contacts[0].point.x + 0.95).abs() < TOLERANCE && (contacts[0].point.y - 0.3).abs() < TOLERANCE);
+        assert_close((position, velocity), (contact.0 + 0.2, contact.1 + 0.2), (1.0, 1.0));
+    }
+
+    #[test]
+    fn ball_grazing_a_cap_bounces_off_its_top() {
+        // drops onto the left plank's top at x = -1 after 0.4 of its travel, and carries on
+        // left, rising again
+        let (position, velocity, contacts) = bounce((-0.8, 0.5), (-1.0, -0.5), 0.5, &planks(), &walls());
+        assert_eq!(contacts.len(), 1);
+        assert!((contacts[0].normal.x).abs() < TOLERANCE && (contacts[0].normal.y - 1.0).abs() < TOLERANCE);
+        assert!((contacts[0].offset - 1.0).abs() < TOLERANCE);
+        assert_close((position, velocity), (-1.3, 0.55), (-1.0, 0.5));
+    }
+
+    #[test]
+    fn ball_passing_just_over_a_cap_is_not_touched() {
+        let (position, velocity, contacts) = bounce((-0.7, 0.41), (-1.0, 0.0), 0.5, &planks(), &walls());
+        assert_close((position, velocity), (-1.2, 0.41), (-1.0, 0.0));
+        assert!(contacts.is_empty());
+    }
+
+    #[test]
+    fn ball_bounces_off_a_plank_and_then_a_wall_in_one_call() {
+        // travels 1.5 diagonally: 0.15 to the left plank's face, 0.75 from there down to the
+        // bottom wall, and the remaining 0.6 back up
+        let (position, velocity, contacts) = bounce((-0.7, 0.0), (-1.0, -1.0), 1.5, &planks(), &walls());
+        assert_close((position, velocity), (0.5, -0.3), (1.0, 1.0));
+        let surfaces : Vec<Surface> = contacts.iter().map(|c| c.surface.clone()).collect();
+        assert_eq!(surfaces, vec![Surface::Plank(0), Surface::Wall(1)]);
+        assert!((contacts[0].time - 0.1).abs() < TOLERANCE);
+        assert!((contacts[0].position.x + 0.85).abs() < TOLERANCE && (contacts[0].position.y + 0.15).abs() < TOLERANCE);
+        assert!((contacts[1].time - 0.6).abs() < TOLERANCE);
+        assert!((contacts[1].position.x + 0.1).abs() < TOLERANCE && (contacts[1].position.y + 0.9).abs() < TOLERANCE);
+    }
+}
@@ -0,0 +1,412 @@
+use remawin::raw::{RawInput, RawInputEvent, RawInputAction};
+use remawin::types::{DeviceType, KeyCode};
+use ron;
+
+use serde::de::DeserializeOwned;
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::cmp::Eq;
+use std::fmt::Debug;
+use std::clone::Clone;
+
+use bindings::{BindingsError, parse_error};
+
+/// A single key or button taking part in a combo. Buttons are numbered per device type, so
+/// the type tells a gamepad button from a mouse button with the same number.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum ComboInput {
+    Key(KeyCode),
+    Button(DeviceType, u32)
+}
+
+impl ComboInput {
+    /// The input and whether it was pressed or released, for key and button raw input.
+    fn from_raw(raw : &RawInput) -> Option<(ComboInput, bool)> {
+        match raw.event {
+            RawInputEvent::Key(ref keycode, ref action, _) =>
+                Some((ComboInput::Key(keycode.clone()), is_press(action))),
+            RawInputEvent::Button(button, _, ref action, _) =>
+                Some((ComboInput::Button(raw.device_type.clone(), button), is_press(action))),
+            _ => None
+        }
+    }
+}
+
+fn is_press(action : &RawInputAction) -> bool {
+    match *action {
+        RawInputAction::Press => true,
+        _ => false
+    }
+}
+
+/// Composite triggers, timed from the timestamps of the raw input.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum Trigger {
+    /// All inputs held at the same time, fired when the last one goes down.
+    Chord(Vec<ComboInput>),
+    /// Inputs pressed in order, with no other key or button in between, the first and last
+    /// press at most `window` seconds apart. A double tap is the same input twice. Presses
+    /// before a completed sequence do not count towards the next one.
+    Sequence(Vec<ComboInput>, f64),
+    /// Input held down for at least the given number of seconds, fired once per hold.
+    Hold(ComboInput, f64)
+}
+
+/// A trigger firing `action`, optionally only while `context` is active.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Combo<ACTION, ID> {
+    #[serde(default)]
+    pub context : Option<ID>,
+    pub trigger : Trigger,
+    pub action : ACTION
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComboEvent<ACTION> {
+    pub action : ACTION,
+    /// Timestamp of the input that completed the combo.
+    pub time : f64
+}
+
+/// Reads a list of combos from a RON file.
+pub fn load_combos<ACTION, ID, P>(path : P) -> Result<Vec<Combo<ACTION, ID>>, BindingsError>
+    where ACTION: DeserializeOwned,
+          ID: DeserializeOwned,
+          P: AsRef<Path> {
+    let path = path.as_ref();
+    let mut data = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut data))
+        .map_err(|err| BindingsError::Io(path.to_path_buf(), err))?;
+    ron::de::from_str(&data)
+        .map_err(|err| parse_error(path, err))
+}
+
+#[derive(Debug, Clone)]
+struct ComboState {
+    /// Held inputs of a chord, or the press time of a held input.
+    held : Vec<(ComboInput, f64)>,
+    fired : bool
+}
+
+/// Evaluates combos against the raw input stream.
+#[derive(Debug, Clone)]
+pub struct ComboTracker<ACTION, ID> {
+    combos : Vec<(Combo<ACTION, ID>, ComboState)>,
+    presses : Vec<(ComboInput, f64)>,
+    /// Inputs currently down, to tell key repeat from new presses.
+    held : Vec<ComboInput>,
+    max_window : f64
+}
+
+impl <ACTION, ID> ComboTracker<ACTION, ID>
+    where ACTION: Clone + Debug,
+          ID: Eq + Clone + Debug {
+
+    pub fn new() -> ComboTracker<ACTION, ID> {
+        ComboTracker {
+            combos : Vec::default(),
+            presses : Vec::default(),
+            held : Vec::default(),
+            max_window : 0.0
+        }
+    }
+
+    pub fn add(&mut self, combo : Combo<ACTION, ID>) {
+        if let Trigger::Sequence(_, window) = combo.trigger {
+            if window > self.max_window {
+                self.max_window = window;
+            }
+        }
+        self.combos.push((combo, ComboState {
+            held : Vec::default(),
+            fired : false
+        }));
+    }
+
+    pub fn clear(&mut self) {
+        self.combos.clear();
+        self.presses.clear();
+        self.held.clear();
+        self.max_window = 0.0;
+    }
+
+    /// Feeds one frame of raw input, and returns the combos it completed. `now` is used for
+    /// holds that complete between input events. Combos whose context is not in `active` are
+    /// not fired, but still track input so they are ready when the context comes back.
+    pub fn process(&mut self, raw_input : &[RawInput], active : &[ID], now : f64) -> Vec<ComboEvent<ACTION>> {
+        let mut fired = Vec::default();
+        for raw in raw_input {
+            let (input, pressed) = match ComboInput::from_raw(raw) {
+                Some(input) => input,
+                None => {
+                    if let RawInputEvent::Focus(false) = raw.event {
+                        // releases that happen while unfocused are never delivered
+                        self.held.clear();
+                        for &mut (_, ref mut state) in self.combos.iter_mut() {
+                            state.held.clear();
+                        }
+                    }
+                    continue
+                }
+            };
+            // key repeat sends more presses while held, which are not new presses
+            let repeat = pressed && self.held.contains(&input);
+            if pressed {
+                if !repeat {
+                    self.held.push(input.clone());
+                    self.presses.push((input.clone(), raw.time));
+                    let oldest = raw.time - self.max_window;
+                    self.presses.retain(|&(_, t)| t >= oldest);
+                }
+            } else {
+                self.held.retain(|i| i != &input);
+            }
+            let mut sequence_complete = false;
+            for &mut (ref combo, ref mut state) in self.combos.iter_mut() {
+                let complete = update_state(&combo.trigger, state, &input, pressed, repeat, raw.time, &self.presses);
+                if complete {
+                    if let Trigger::Sequence(..) = combo.trigger {
+                        sequence_complete = true;
+                    }
+                    if is_active(combo, active) {
+                        fired.push(ComboEvent {
+                            action : combo.action.clone(),
+                            time : raw.time
+                        });
+                    }
+                }
+            }
+            if sequence_complete {
+                self.presses.clear();
+            }
+        }
+        for &mut (ref combo, ref mut state) in self.combos.iter_mut() {
+            if let Trigger::Hold(_, duration) = combo.trigger {
+                if let Some(&(_, pressed_at)) = state.held.first() {
+                    if !state.fired && now - pressed_at >= duration {
+                        state.fired = true;
+                        if is_active(combo, active) {
+                            fired.push(ComboEvent {
+                                action : combo.action.clone(),
+                                time : pressed_at + duration
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        fired
+    }
+}
+
+fn is_active<ACTION, ID>(combo : &Combo<ACTION, ID>, active : &[ID]) -> bool
+    where ID: Eq {
+    match combo.context {
+        Some(ref context) => active.contains(context),
+        None => true
+    }
+}
+
+/// Updates the state of one combo for a press or release, returning true if it completed.
+fn update_state(trigger : &Trigger,
+                state : &mut ComboState,
+                input : &ComboInput,
+                pressed : bool,
+                repeat : bool,
+                t : f64,
+                presses : &[(ComboInput, f64)]) -> bool {
+    match trigger {
+        &Trigger::Chord(ref inputs) => {
+            if !inputs.contains(input) {
+                return false;
+            }
+            if pressed {
+                if !state.held.iter().any(|&(ref i, _)| i == input) {
+                    state.held.push((input.clone(), t));
+                }
+            } else {
+                state.held.retain(|&(ref i, _)| i != input);
+                state.fired = false;
+            }
+            let complete = inputs.iter().all(|i| state.held.iter().any(|&(ref h, _)| h == i));
+            if pressed && complete && !state.fired {
+                state.fired = true;
+                true
+            } else {
+                false
+            }
+        },
+        &Trigger::Sequence(ref inputs, window) => {
+            if !pressed || repeat || inputs.len() == 0 || presses.len() < inputs.len() {
+                return false;
+            }
+            let recent = &presses[presses.len() - inputs.len()..];
+            let in_order = recent.iter().zip(inputs.iter()).all(|(&(ref p, _), i)| p == i);
+            in_order && t - recent[0].1 <= window
+        },
+        &Trigger::Hold(ref held, _) => {
+            if held == input {
+                if pressed {
+                    if state.held.len() == 0 {
+                        state.held.push((input.clone(), t));
+                        state.fired = false;
+                    }
+                } else {
+                    state.held.clear();
+                }
+            }
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use remawin::raw::RawInputModifiers;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum TestAction {
+        Dodge,
+        Special,
+        Charge,
+        Quit
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum TestContext {
+        Default,
+        Menu
+    }
+
+    type TestTracker = ComboTracker<TestAction, TestContext>;
+
+    fn tracker(triggers : Vec<(Trigger, TestAction, Option<TestContext>)>) -> TestTracker {
+        let mut tracker = TestTracker::new();
+        for (trigger, action, context) in triggers {
+            tracker.add(Combo {
+                context : context,
+                trigger : trigger,
+                action : action
+            });
+        }
+        tracker
+    }
+
+    fn key(t : f64, keycode : KeyCode, action : RawInputAction) -> RawInput {
+        RawInput::new(t, DeviceType::Keyboard, 0, RawInputEvent::Key(keycode, action, RawInputModifiers::empty()))
+    }
+
+    /// Presses and releases each key in turn, a quarter of a second apart from `t`, and
+    /// returns the combos fired.
+    fn tap(tracker : &mut TestTracker, t : f64, keys : &[KeyCode]) -> Vec<(TestAction, f64)> {
+        let mut raw_input = Vec::default();
+        for (i, keycode) in keys.iter().enumerate() {
+            let pressed_at = t + 0.25 * i as f64;
+            raw_input.push(key(pressed_at, keycode.clone(), RawInputAction::Press));
+            raw_input.push(key(pressed_at + 0.125, keycode.clone(), RawInputAction::Release));
+        }
+        let now = raw_input.last().map(|raw| raw.time).unwrap_or(t);
+        fired(tracker.process(&raw_input, &[TestContext::Default], now))
+    }
+
+    fn fired(events : Vec<ComboEvent<TestAction>>) -> Vec<(TestAction, f64)> {
+        events.into_iter().map(|event| (event.action, event.time)).collect()
+    }
+
+    fn double_tap() -> TestTracker {
+        tracker(vec![(Trigger::Sequence(vec![ComboInput::Key(KeyCode::A), ComboInput::Key(KeyCode::A)], 0.3),
+                      TestAction::Dodge, None)])
+    }
+
+    #[test]
+    fn double_tap_fires_inside_its_window() {
+        let mut tracker = double_tap();
+        assert_eq!(tap(&mut tracker, 1.0, &[KeyCode::A, KeyCode::A]), vec![(TestAction::Dodge, 1.25)]);
+        // the taps of a completed sequence do not start the next one
+        assert!(tap(&mut tracker, 1.5, &[KeyCode::A]).is_empty());
+    }
+
+    #[test]
+    fn double_tap_does_not_fire_outside_its_window() {
+        let mut tracker = double_tap();
+        assert!(tap(&mut tracker, 1.0, &[KeyCode::A]).is_empty());
+        assert!(tap(&mut tracker, 1.5, &[KeyCode::A]).is_empty());
+        // the second tap can still start a double tap of its own
+        assert_eq!(tap(&mut tracker, 1.75, &[KeyCode::A]), vec![(TestAction::Dodge, 1.75)]);
+    }
+
+    #[test]
+    fn sequence_only_fires_in_order_and_uninterrupted() {
+        let mut tracker = tracker(vec![
+            (Trigger::Sequence(vec![ComboInput::Key(KeyCode::Down),
+                                    ComboInput::Key(KeyCode::Right),
+                                    ComboInput::Key(KeyCode::J)], 1.0),
+             TestAction::Special, None)]);
+        assert!(tap(&mut tracker, 1.0, &[KeyCode::Right, KeyCode::Down, KeyCode::J]).is_empty());
+        assert!(tap(&mut tracker, 3.0, &[KeyCode::Down, KeyCode::X, KeyCode::Right, KeyCode::J]).is_empty());
+        assert_eq!(tap(&mut tracker, 5.0, &[KeyCode::Down, KeyCode::Right, KeyCode::J]),
+                   vec![(TestAction::Special, 5.5)]);
+    }
+
+    fn charge() -> TestTracker {
+        tracker(vec![(Trigger::Hold(ComboInput::Key(KeyCode::Space), 0.5), TestAction::Charge, None)])
+    }
+
+    #[test]
+    fn hold_released_early_does_not_fire() {
+        let mut tracker = charge();
+        let active = [TestContext::Default];
+        assert!(tracker.process(&[key(1.0, KeyCode::Space, RawInputAction::Press)], &active, 1.0).is_empty());
+        assert!(tracker.process(&[], &active, 1.25).is_empty());
+        assert!(tracker.process(&[key(1.375, KeyCode::Space, RawInputAction::Release)], &active, 1.375).is_empty());
+        assert!(tracker.process(&[], &active, 2.0).is_empty());
+    }
+
+    #[test]
+    fn hold_fires_once_at_its_duration() {
+        let mut tracker = charge();
+        let active = [TestContext::Default];
+        tracker.process(&[key(1.0, KeyCode::Space, RawInputAction::Press)], &active, 1.0);
+        // the frame comes later than the hold completed, which is when it is reported
+        assert_eq!(fired(tracker.process(&[], &active, 1.75)), vec![(TestAction::Charge, 1.5)]);
+        assert!(tracker.process(&[], &active, 2.5).is_empty());
+    }
+
+    #[test]
+    fn key_repeat_is_not_a_new_press() {
+        let active = [TestContext::Default];
+        let mut charge = charge();
+        charge.process(&[key(1.0, KeyCode::Space, RawInputAction::Press)], &active, 1.0);
+        charge.process(&[key(1.25, KeyCode::Space, RawInputAction::Press)], &active, 1.25);
+        assert_eq!(fired(charge.process(&[], &active, 1.5)), vec![(TestAction::Charge, 1.5)]);
+
+        let mut double_tap = double_tap();
+        let repeated = [key(1.0, KeyCode::A, RawInputAction::Press),
+                        key(1.1, KeyCode::A, RawInputAction::Press),
+                        key(1.2, KeyCode::A, RawInputAction::Press)];
+        assert!(double_tap.process(&repeated, &active, 1.2).is_empty());
+    }
+
+    #[test]
+    fn combos_only_fire_in_their_context() {
+        let mut tracker = tracker(vec![
+            (Trigger::Chord(vec![ComboInput::Key(KeyCode::LControl), ComboInput::Key(KeyCode::Q)]),
+             TestAction::Quit, Some(TestContext::Menu))]);
+        let control = key(1.0, KeyCode::LControl, RawInputAction::Press);
+        let q = key(1.1, KeyCode::Q, RawInputAction::Press);
+        assert!(tracker.process(&[control, q], &[TestContext::Default], 1.1).is_empty());
+
+        // Control stays held while the context changes, and counts towards the chord
+        let released = key(1.2, KeyCode::Q, RawInputAction::Release);
+        let pressed = key(1.3, KeyCode::Q, RawInputAction::Press);
+        let active = [TestContext::Default, TestContext::Menu];
+        assert_eq!(fired(tracker.process(&[released, pressed], &active, 1.3)), vec![(TestAction::Quit, 1.3)]);
+        // and the chord does not fire again on repeat
+        let repeated = key(1.4, KeyCode::Q, RawInputAction::Press);
+        assert!(tracker.process(&[repeated], &active, 1.4).is_empty());
+    }
+}
@@ -0,0 +1,105 @@
+use amethyst::ecs::resources::{Camera, Projection, ScreenDimensions};
+use cgmath::Point2;
+
+/// Unprojects a cursor position in [0, 1] window coordinates, with y pointing down as in
+/// `ActionArgument::CursorPosition` and `RawInputEvent::CursorPosition`, into world space.
+/// Only orthographic cameras looking down the z axis with y up are supported, for other
+/// projections there is no single world position and `None` is returned.
+pub fn cursor_to_world(cursor : (f64, f64), camera : &Camera) -> Option<Point2<f32>> {
+    match camera.proj {
+        Projection::Orthographic { left, right, bottom, top, .. } => {
+            let (x, y) = (cursor.0 as f32, cursor.1 as f32);
+            Some(Point2::new(camera.eye[0] + left + x * (right - left),
+                             camera.eye[1] + top - y * (top - bottom)))
+        },
+        _ => None
+    }
+}
+
+/// Like `cursor_to_world`, for a cursor position in pixels.
+pub fn pixels_to_world(pixels : (f64, f64),
+                       dimensions : &ScreenDimensions,
+                       camera : &Camera) -> Option<Point2<f32>> {
+    normalise(pixels, (dimensions.w as f64, dimensions.h as f64))
+        .and_then(|cursor| cursor_to_world(cursor, camera))
+}
+
+/// True if positions can be normalised by `size`, that is both sides are finite and above zero.
+/// Minimised windows report a size of zero.
+pub fn is_valid_size(size : (f64, f64)) -> bool {
+    size.0.is_finite() && size.1.is_finite() && size.0 > 0.0 && size.1 > 0.0
+}
+
+/// Divides a position or offset in pixels by the window size, or `None` if the size is not
+/// valid or the input is not finite.
+pub fn normalise(pixels : (f64, f64), size : (f64, f64)) -> Option<(f64, f64)> {
+    if !is_valid_size(size) || !pixels.0.is_finite() || !pixels.1.is_finite() {
+        return None;
+    }
+    Some((pixels.0 / size.0, pixels.1 / size.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sees 4 by 2 world units around (3, -0.5).
+    fn camera() -> Camera {
+        Camera {
+            proj : Projection::Orthographic {
+                left : -2.0,
+                right : 2.0,
+                bottom : -1.0,
+                top : 1.0,
+                near : 0.0,
+                far : 1.0
+            },
+            eye : [3.0, -0.5, 0.1],
+            target : [3.0, -0.5, 0.0],
+            up : [0.0, 1.0, 0.0]
+        }
+    }
+
+    #[test]
+    fn window_corners_map_to_the_projection_bounds() {
+        let camera = camera();
+        // the window's y points down, the world's up
+        assert_eq!(cursor_to_world((0.0, 0.0), &camera), Some(Point2::new(1.0, 0.5)));
+        assert_eq!(cursor_to_world((1.0, 0.0), &camera), Some(Point2::new(5.0, 0.5)));
+        assert_eq!(cursor_to_world((0.0, 1.0), &camera), Some(Point2::new(1.0, -1.5)));
+        assert_eq!(cursor_to_world((1.0, 1.0), &camera), Some(Point2::new(5.0, -1.5)));
+        assert_eq!(cursor_to_world((0.5, 0.5), &camera), Some(Point2::new(3.0, -0.5)));
+    }
+
+    #[test]
+    fn perspective_cameras_have_no_cursor_position() {
+        let camera = Camera {
+            proj : Projection::Perspective {
+                fov : 60.0,
+                aspect_ratio : 1.0,
+                near : 0.1,
+                far : 100.0
+            },
+            .. camera()
+        };
+        assert_eq!(cursor_to_world((0.5, 0.5), &camera), None);
+    }
+
+    #[test]
+    fn only_finite_sizes_above_zero_are_valid() {
+        assert!(is_valid_size((800.0, 600.0)));
+        assert!(!is_valid_size((0.0, 0.0)));
+        assert!(!is_valid_size((800.0, 0.0)));
+        assert!(!is_valid_size((-800.0, 600.0)));
+        assert!(!is_valid_size((::std::f64::INFINITY, 600.0)));
+        assert!(!is_valid_size((800.0, ::std::f64::NAN)));
+    }
+
+    #[test]
+    fn normalising_needs_a_valid_size_and_finite_input() {
+        assert_eq!(normalise((200.0, 150.0), (800.0, 600.0)), Some((0.25, 0.25)));
+        assert_eq!(normalise((-200.0, 900.0), (800.0, 600.0)), Some((-0.25, 1.5)));
+        assert_eq!(normalise((200.0, 150.0), (0.0, 0.0)), None);
+        assert_eq!(normalise((::std::f64::NAN, 150.0), (800.0, 600.0)), None);
+    }
+}
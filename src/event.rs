@@ -0,0 +1,78 @@
+use remawin;
+use shrev::{Event, EventHandler};
+
+use combo::ComboEvent;
+use axis::AxisEvent;
+use text::TextEvent;
+
+use std::hash::Hash;
+use std::cmp::Eq;
+use std::fmt::Debug;
+use std::clone::Clone;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IWindowEvent {
+    pub payload : remawin::WindowEvent
+}
+
+impl IWindowEvent {
+    pub fn new(event: remawin::WindowEvent) -> IWindowEvent {
+        IWindowEvent {
+            payload : event
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ControllerEvent<ACTION, ID>
+    where ACTION: Hash + Eq + Clone + Debug,
+          ID: Hash + Eq + Clone + Debug {
+    pub player : Option<usize>,
+    pub payload : remawin::ControllerEvent<ACTION, ID>
+}
+
+impl <ACTION, ID> ControllerEvent<ACTION, ID>
+    where ACTION: Hash + Eq + Clone + Debug,
+          ID: Hash + Eq + Clone + Debug {
+
+    pub fn new(event: remawin::ControllerEvent<ACTION, ID>) -> ControllerEvent<ACTION, ID> {
+        ControllerEvent {
+            player : None,
+            payload : event
+        }
+    }
+
+    pub fn for_player(player: Option<usize>,
+                      event: remawin::ControllerEvent<ACTION, ID>) -> ControllerEvent<ACTION, ID> {
+        ControllerEvent {
+            player : player,
+            payload : event
+        }
+    }
+}
+
+impl <ACTION, ID> Event for ControllerEvent<ACTION, ID>
+    where ACTION: Hash + Eq + Clone + Debug + Send + Sync + 'static,
+          ID: Hash + Eq + Clone + Debug + Send + Sync + 'static {}
+
+impl <ACTION> Event for ComboEvent<ACTION>
+    where ACTION: Clone + Debug + Send + Sync + 'static {}
+
+impl <ACTION> Event for AxisEvent<ACTION>
+    where ACTION: Clone + Debug + Send + Sync + 'static {}
+
+impl Event for IWindowEvent {}
+
+impl Event for TextEvent {}
+
+pub fn init_event_system<ACTION, ID>() -> EventHandler
+    where ACTION: Hash + Eq + Clone + Debug + Send + Sync + 'static,
+          ID: Hash + Eq + Clone + Debug + Send + Sync + 'static {
+    let mut event_handler = EventHandler::new();
+    event_handler.register::<IWindowEvent>();
+    event_handler.register::<ControllerEvent<ACTION, ID>>();
+    event_handler.register::<ComboEvent<ACTION>>();
+    event_handler.register::<AxisEvent<ACTION>>();
+    event_handler.register::<TextEvent>();
+    event_handler
+}
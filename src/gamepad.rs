@@ -0,0 +1,503 @@
+use remawin::raw::{RawInput, RawInputEvent, RawInputAction, RawInputModifiers};
+use remawin::types::DeviceType;
+use time;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "gilrs")]
+use std::sync::mpsc::{self, Receiver};
+#[cfg(feature = "gilrs")]
+use std::thread;
+#[cfg(feature = "gilrs")]
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "gilrs")]
+use gilrs::{Gilrs, EventType, Button, Axis};
+
+/// Stick deflection needed before a stick direction button is pressed.
+const STICK_PRESS_THRESHOLD : f32 = 0.5;
+/// Stick deflection below which a pressed stick direction button is released again.
+const STICK_RELEASE_THRESHOLD : f32 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    Select,
+    Start,
+    Mode,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    Other(u32)
+}
+
+impl GamepadButton {
+    /// Button number used in `RawInputEvent::Button` for this button.
+    pub fn code(&self) -> u32 {
+        match *self {
+            GamepadButton::South => 1,
+            GamepadButton::East => 2,
+            GamepadButton::North => 3,
+            GamepadButton::West => 4,
+            GamepadButton::LeftBumper => 5,
+            GamepadButton::RightBumper => 6,
+            GamepadButton::Select => 7,
+            GamepadButton::Start => 8,
+            GamepadButton::Mode => 9,
+            GamepadButton::LeftStick => 10,
+            GamepadButton::RightStick => 11,
+            GamepadButton::DPadUp => 12,
+            GamepadButton::DPadDown => 13,
+            GamepadButton::DPadLeft => 14,
+            GamepadButton::DPadRight => 15,
+            GamepadButton::Other(b) => b,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+    Other(u32)
+}
+
+impl GamepadAxis {
+    /// Axis number used in `RawInputEvent::Axis` for this axis.
+    pub fn code(&self) -> u32 {
+        match *self {
+            GamepadAxis::LeftStickX => 0,
+            GamepadAxis::LeftStickY => 1,
+            GamepadAxis::RightStickX => 2,
+            GamepadAxis::RightStickY => 3,
+            GamepadAxis::LeftTrigger => 4,
+            GamepadAxis::RightTrigger => 5,
+            GamepadAxis::Other(a) => a,
+        }
+    }
+
+    /// Virtual buttons pressed when the stick is pushed in the negative and positive direction,
+    /// so that sticks can drive `State` actions the same way as keys.
+    fn direction_buttons(&self) -> Option<(u32, u32)> {
+        match *self {
+            GamepadAxis::LeftStickX => Some((LEFT_STICK_LEFT, LEFT_STICK_RIGHT)),
+            GamepadAxis::LeftStickY => Some((LEFT_STICK_DOWN, LEFT_STICK_UP)),
+            GamepadAxis::RightStickX => Some((RIGHT_STICK_LEFT, RIGHT_STICK_RIGHT)),
+            GamepadAxis::RightStickY => Some((RIGHT_STICK_DOWN, RIGHT_STICK_UP)),
+            _ => None
+        }
+    }
+}
+
+/// Button numbers of the virtual stick direction buttons.
+pub const LEFT_STICK_UP : u32 = 100;
+pub const LEFT_STICK_DOWN : u32 = 101;
+pub const LEFT_STICK_LEFT : u32 = 102;
+pub const LEFT_STICK_RIGHT : u32 = 103;
+pub const RIGHT_STICK_UP : u32 = 104;
+pub const RIGHT_STICK_DOWN : u32 = 105;
+pub const RIGHT_STICK_LEFT : u32 = 106;
+pub const RIGHT_STICK_RIGHT : u32 = 107;
+
+/// Gamepad events, tagged with the id of the pad that produced them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GamepadEvent {
+    Connected(u32),
+    Disconnected(u32),
+    Button(u32, GamepadButton, bool),
+    Axis(u32, GamepadAxis, f32)
+}
+
+/// Source of gamepad events, polled once per frame by `AmethystEventMapper::process`.
+pub trait GamepadSource: Send + Sync {
+    /// Events since the last poll, each with the time it happened on the
+    /// `time::precise_time_s` clock.
+    fn poll(&mut self) -> Vec<(f64, GamepadEvent)>;
+
+    /// True if the event times are when real devices produced them, so they can be used to
+    /// measure input latency.
+    fn is_live(&self) -> bool {
+        true
+    }
+}
+
+/// Gamepad source fed by hand, for tests and scripted input. Clones share their queue, so a
+/// clone kept as a handle can push events after the source is given to the mapper.
+#[derive(Debug, Clone, Default)]
+pub struct SyntheticGamepadSource {
+    events : Arc<Mutex<VecDeque<(f64, GamepadEvent)>>>
+}
+
+impl SyntheticGamepadSource {
+    pub fn new() -> SyntheticGamepadSource {
+        SyntheticGamepadSource {
+            events : Arc::new(Mutex::new(VecDeque::new()))
+        }
+    }
+
+    /// Queues an event happening now.
+    pub fn push(&self, event : GamepadEvent) {
+        self.push_at(time::precise_time_s(), event);
+    }
+
+    /// Queues an event that happened at `t`, on the `time::precise_time_s` clock.
+    pub fn push_at(&self, t : f64, event : GamepadEvent) {
+        self.events.lock().unwrap().push_back((t, event));
+    }
+}
+
+impl GamepadSource for SyntheticGamepadSource {
+    fn poll(&mut self) -> Vec<(f64, GamepadEvent)> {
+        self.events.lock().unwrap().drain(..).collect()
+    }
+
+    fn is_live(&self) -> bool {
+        false
+    }
+}
+
+/// Time between two polls of gilrs on the gamepad thread, in milliseconds.
+#[cfg(feature = "gilrs")]
+const GILRS_POLL_INTERVAL : u64 = 2;
+
+/// Gamepads read through gilrs. Gilrs is not thread safe, so it runs on its own thread and
+/// hands events over through a channel, with the time gilrs received them.
+#[cfg(feature = "gilrs")]
+pub struct GilrsSource {
+    events : Mutex<Receiver<(f64, GamepadEvent)>>
+}
+
+#[cfg(feature = "gilrs")]
+impl GilrsSource {
+    /// Starts the gamepad thread. Fails if gilrs cannot be initialised on this platform.
+    pub fn new() -> Result<GilrsSource, String> {
+        let (sender, receiver) = mpsc::channel();
+        let (started, start) = mpsc::channel();
+        thread::spawn(move || {
+            let mut gilrs = match Gilrs::new() {
+                Ok(gilrs) => gilrs,
+                Err(err) => {
+                    let _ = started.send(Err(format!("{}", err)));
+                    return;
+                }
+            };
+            // pads plugged in before the start have no connected event of their own
+            let now = time::precise_time_s();
+            let connected : Vec<(f64, GamepadEvent)> = gilrs.gamepads()
+                .map(|(id, _)| (now, GamepadEvent::Connected(id as u32)))
+                .collect();
+            let _ = started.send(Ok(()));
+            for event in connected {
+                if sender.send(event).is_err() {
+                    return;
+                }
+            }
+            loop {
+                while let Some(event) = gilrs.next_event() {
+                    let t = precise_time(event.time);
+                    if let Some(event) = from_gilrs(event.id as u32, event.event) {
+                        // the source was dropped
+                        if sender.send((t, event)).is_err() {
+                            return;
+                        }
+                    }
+                }
+                thread::sleep(Duration::from_millis(GILRS_POLL_INTERVAL));
+            }
+        });
+        match start.recv() {
+            Ok(Ok(())) => Ok(GilrsSource {
+                events : Mutex::new(receiver)
+            }),
+            Ok(Err(err)) => Err(err),
+            Err(_) => Err("the gamepad thread stopped while starting".to_string())
+        }
+    }
+}
+
+#[cfg(feature = "gilrs")]
+impl GamepadSource for GilrsSource {
+    fn poll(&mut self) -> Vec<(f64, GamepadEvent)> {
+        self.events.lock().unwrap().try_iter().collect()
+    }
+}
+
+/// A wall clock time from gilrs on the `time::precise_time_s` clock, by how long ago it was.
+#[cfg(feature = "gilrs")]
+fn precise_time(t : SystemTime) -> f64 {
+    let now = time::precise_time_s();
+    match t.elapsed() {
+        Ok(age) => now - (age.as_secs() as f64 + age.subsec_nanos() as f64 / 1.0e9),
+        // the wall clock went back since, the event is as recent as it gets
+        Err(_) => now
+    }
+}
+
+#[cfg(feature = "gilrs")]
+fn from_gilrs(id : u32, event : EventType) -> Option<GamepadEvent> {
+    match event {
+        EventType::Connected => Some(GamepadEvent::Connected(id)),
+        EventType::Disconnected => Some(GamepadEvent::Disconnected(id)),
+        EventType::ButtonPressed(button, _) =>
+            gilrs_button(button).map(|button| GamepadEvent::Button(id, button, true)),
+        EventType::ButtonReleased(button, _) =>
+            gilrs_button(button).map(|button| GamepadEvent::Button(id, button, false)),
+        EventType::AxisChanged(axis, value, _) =>
+            gilrs_axis(axis).map(|axis| GamepadEvent::Axis(id, axis, value)),
+        _ => None
+    }
+}
+
+#[cfg(feature = "gilrs")]
+fn gilrs_button(button : Button) -> Option<GamepadButton> {
+    match button {
+        Button::South => Some(GamepadButton::South),
+        Button::East => Some(GamepadButton::East),
+        Button::North => Some(GamepadButton::North),
+        Button::West => Some(GamepadButton::West),
+        Button::LeftTrigger => Some(GamepadButton::LeftBumper),
+        Button::RightTrigger => Some(GamepadButton::RightBumper),
+        Button::Select => Some(GamepadButton::Select),
+        Button::Start => Some(GamepadButton::Start),
+        Button::Mode => Some(GamepadButton::Mode),
+        Button::LeftThumb => Some(GamepadButton::LeftStick),
+        Button::RightThumb => Some(GamepadButton::RightStick),
+        Button::DPadUp => Some(GamepadButton::DPadUp),
+        Button::DPadDown => Some(GamepadButton::DPadDown),
+        Button::DPadLeft => Some(GamepadButton::DPadLeft),
+        Button::DPadRight => Some(GamepadButton::DPadRight),
+        _ => None
+    }
+}
+
+#[cfg(feature = "gilrs")]
+fn gilrs_axis(axis : Axis) -> Option<GamepadAxis> {
+    match axis {
+        Axis::LeftStickX => Some(GamepadAxis::LeftStickX),
+        Axis::LeftStickY => Some(GamepadAxis::LeftStickY),
+        Axis::RightStickX => Some(GamepadAxis::RightStickX),
+        Axis::RightStickY => Some(GamepadAxis::RightStickY),
+        Axis::LeftZ => Some(GamepadAxis::LeftTrigger),
+        Axis::RightZ => Some(GamepadAxis::RightTrigger),
+        _ => None
+    }
+}
+
+/// Connected pads, and the buttons currently held on each of them.
+#[derive(Debug, Default)]
+pub struct GamepadState {
+    pressed : HashMap<u32, HashSet<u32>>
+}
+
+impl GamepadState {
+    pub fn new() -> GamepadState {
+        GamepadState {
+            pressed : HashMap::new()
+        }
+    }
+
+    pub fn connected(&self) -> Vec<u32> {
+        let mut ids : Vec<u32> = self.pressed.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    pub fn process_event(&mut self, event : &GamepadEvent, t : f64) -> Vec<RawInput> {
+        match event {
+            &GamepadEvent::Connected(id) => {
+                self.pressed.entry(id).or_insert_with(HashSet::new);
+                vec![RawInput::new(t, DeviceType::Gamepad, id, RawInputEvent::Connected)]
+            },
+            &GamepadEvent::Disconnected(id) => {
+                // release everything still held, so no state action is left active
+                let mut raw : Vec<RawInput> = match self.pressed.remove(&id) {
+                    Some(held) => {
+                        let mut held : Vec<u32> = held.into_iter().collect();
+                        held.sort();
+                        held.into_iter()
+                            .map(|button| button_input(t, id, button, RawInputAction::Release))
+                            .collect()
+                    },
+                    None => Vec::default()
+                };
+                raw.push(RawInput::new(t, DeviceType::Gamepad, id, RawInputEvent::Disconnected));
+                raw
+            },
+            &GamepadEvent::Button(id, button, pressed) => {
+                match self.set_pressed(id, button.code(), pressed) {
+                    Some(action) => vec![button_input(t, id, button.code(), action)],
+                    None => Vec::default()
+                }
+            },
+            &GamepadEvent::Axis(id, axis, value) => {
+                let mut raw = vec![RawInput::new(t, DeviceType::Gamepad, id,
+                                                 RawInputEvent::Axis(axis.code(), value as f64))];
+                if let Some((negative, positive)) = axis.direction_buttons() {
+                    for &(button, deflection) in &[(negative, -value), (positive, value)] {
+                        let held = self.is_pressed(id, button);
+                        let pressed = if held {
+                            deflection > STICK_RELEASE_THRESHOLD
+                        } else {
+                            deflection > STICK_PRESS_THRESHOLD
+                        };
+                        if let Some(action) = self.set_pressed(id, button, pressed) {
+                            raw.push(button_input(t, id, button, action));
+                        }
+                    }
+                }
+                raw
+            }
+        }
+    }
+
+    fn is_pressed(&self, id : u32, button : u32) -> bool {
+        match self.pressed.get(&id) {
+            Some(held) => held.contains(&button),
+            None => false
+        }
+    }
+
+    /// Updates the held state of a button, and returns the action to report if it changed.
+    fn set_pressed(&mut self, id : u32, button : u32, pressed : bool) -> Option<RawInputAction> {
+        let held = self.pressed.entry(id).or_insert_with(HashSet::new);
+        if pressed && held.insert(button) {
+            Some(RawInputAction::Press)
+        } else if !pressed && held.remove(&button) {
+            Some(RawInputAction::Release)
+        } else {
+            None
+        }
+    }
+}
+
+fn button_input(t : f64, id : u32, button : u32, action : RawInputAction) -> RawInput {
+    RawInput::new(t, DeviceType::Gamepad, id,
+                  RawInputEvent::Button(button, (0.0, 0.0), action, RawInputModifiers::empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use remawin::{ActionMetadata, ActionArgument, MappedType};
+    use input_mapper::AmethystEventMapper;
+
+    #[allow(dead_code)]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    enum TestAction {
+        Jump
+    }
+
+    impl ActionMetadata for TestAction {
+        fn mapped_type(&self) -> MappedType {
+            MappedType::Action
+        }
+
+        fn args(&self) -> Vec<ActionArgument> {
+            Vec::default()
+        }
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    enum TestContext {
+        Default
+    }
+
+    /// Pad id, button and action of gamepad button input.
+    fn buttons(raw_input : &[RawInput]) -> Vec<(u32, u32, RawInputAction)> {
+        raw_input.iter()
+            .filter(|raw| raw.device_type == DeviceType::Gamepad)
+            .filter_map(|raw| match raw.event {
+                RawInputEvent::Button(button, _, ref action, _) => Some((raw.device_id, button, action.clone())),
+                _ => None
+            })
+            .collect()
+    }
+
+    #[test]
+    fn synthetic_source_clones_share_their_queue() {
+        let handle = SyntheticGamepadSource::new();
+        let mut source = handle.clone();
+        handle.push_at(1.0, GamepadEvent::Connected(0));
+        handle.push_at(2.0, GamepadEvent::Button(0, GamepadButton::South, true));
+        assert_eq!(source.poll(), vec![(1.0, GamepadEvent::Connected(0)),
+                                       (2.0, GamepadEvent::Button(0, GamepadButton::South, true))]);
+        assert_eq!(source.poll(), Vec::default());
+    }
+
+    #[test]
+    fn buttons_report_changes_only() {
+        let mut state = GamepadState::new();
+        let pressed = state.process_event(&GamepadEvent::Button(0, GamepadButton::South, true), 1.0);
+        assert_eq!(buttons(&pressed), vec![(0, 1, RawInputAction::Press)]);
+        assert_eq!(pressed[0].time, 1.0);
+        assert!(state.process_event(&GamepadEvent::Button(0, GamepadButton::South, true), 2.0).is_empty());
+        let released = state.process_event(&GamepadEvent::Button(0, GamepadButton::South, false), 3.0);
+        assert_eq!(buttons(&released), vec![(0, 1, RawInputAction::Release)]);
+    }
+
+    #[test]
+    fn stick_directions_press_and_release_with_hysteresis() {
+        let mut state = GamepadState::new();
+        let deflect = |state : &mut GamepadState, value| {
+            buttons(&state.process_event(&GamepadEvent::Axis(2, GamepadAxis::LeftStickY, value), 0.0))
+        };
+        assert_eq!(deflect(&mut state, 0.4), vec![]);
+        assert_eq!(deflect(&mut state, 0.6), vec![(2, LEFT_STICK_UP, RawInputAction::Press)]);
+        assert_eq!(deflect(&mut state, 0.4), vec![]);
+        assert_eq!(deflect(&mut state, 0.2), vec![(2, LEFT_STICK_UP, RawInputAction::Release)]);
+        assert_eq!(deflect(&mut state, -0.6), vec![(2, LEFT_STICK_DOWN, RawInputAction::Press)]);
+    }
+
+    #[test]
+    fn disconnecting_releases_held_buttons() {
+        let mut state = GamepadState::new();
+        state.process_event(&GamepadEvent::Connected(1), 0.0);
+        state.process_event(&GamepadEvent::Button(1, GamepadButton::East, true), 0.0);
+        state.process_event(&GamepadEvent::Button(1, GamepadButton::South, true), 0.0);
+        assert_eq!(state.connected(), vec![1]);
+        let raw = state.process_event(&GamepadEvent::Disconnected(1), 1.0);
+        assert_eq!(buttons(&raw), vec![(1, 1, RawInputAction::Release), (1, 2, RawInputAction::Release)]);
+        match raw.last().unwrap().event {
+            RawInputEvent::Disconnected => (),
+            ref other => panic!("expected a disconnect last, got {:?}", other)
+        };
+        assert!(state.connected().is_empty());
+    }
+
+    #[test]
+    fn mapper_polls_an_installed_synthetic_source() {
+        let handle = SyntheticGamepadSource::new();
+        let mut mapper = AmethystEventMapper::<TestAction, TestContext>::new((800.0, 600.0));
+        mapper.with_gamepad_source(handle.clone());
+        handle.push(GamepadEvent::Connected(1));
+        handle.push(GamepadEvent::Button(1, GamepadButton::Start, true));
+        let raw = mapper.process_events(&Vec::new());
+        assert_eq!(buttons(&raw), vec![(1, GamepadButton::Start.code(), RawInputAction::Press)]);
+        assert_eq!(mapper.connected_gamepads(), vec![1]);
+        assert!(mapper.process_events(&Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn mapped_gamepad_input_keeps_the_source_time() {
+        let handle = SyntheticGamepadSource::new();
+        let mut mapper = AmethystEventMapper::<TestAction, TestContext>::new((800.0, 600.0));
+        mapper.with_gamepad_source(handle.clone());
+        handle.push_at(5.0, GamepadEvent::Button(0, GamepadButton::South, true));
+        let raw = mapper.process_events(&Vec::new());
+        assert_eq!(buttons(&raw), vec![(0, GamepadButton::South.code(), RawInputAction::Press)]);
+        assert_eq!(raw[0].time, 5.0);
+    }
+}
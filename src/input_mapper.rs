@@ -0,0 +1,1789 @@
+use amethyst::{WindowEvent, Event};
+use amethyst;
+use glutin::{MouseScrollDelta, Touch};
+use time;
+use remawin;
+use remawin::raw::{RawInput, RawInputEvent, RawInputAction, RawInputModifiers};
+use remawin::types::{DeviceType, WindowData, ActionMetadata, KeyCode, RawType};
+
+use gamepad::{GamepadEvent, GamepadSource, GamepadState};
+use touch::{TouchRegion, TouchState, load_touch_regions};
+use player::Player;
+use combo::{Combo, ComboEvent, ComboTracker, load_combos};
+use axis::{AxisDirection, AxisEvent, AxisRebindOutcome, AxisTracker, VirtualAxis, load_layered_axes,
+           rebind_axis};
+use recording::{InputRecording, InputReplay};
+use text::{TextBuffer, TextEvent, TextInput};
+use input_state::InputState;
+use metrics::InputMetrics;
+use cursor::{is_valid_size, normalise};
+use bindings::{BindingsError, BindingsIssue, BindingsWatcher, Mapping, MappingArgs, Modifier,
+               RebindOutcome, load_layered_bindings, rebind};
+use remapper::{BindingsReMapper, HeldInput};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use std::hash::Hash;
+use std::cmp::Eq;
+use std::fmt::Debug;
+use std::clone::Clone;
+use std::default::Default;
+use std::path::{Path, PathBuf};
+
+pub struct AmethystEventMapper<ACTION, ID>
+    where ACTION: Hash + Eq + Clone,
+          ID: Hash + Eq + Clone + Debug {
+    input_remapper : BindingsReMapper<ACTION, ID>,
+    window_data : WindowData,
+    /// Size the window reports, switched to when focus comes back.
+    reported_size : Option<(f64, f64)>,
+    modifiers : ModifierState,
+    scroll : ScrollState,
+    gamepads : GamepadState,
+    touches : TouchState,
+    gamepad_source : Option<Box<GamepadSource>>,
+    players : Vec<Player<ACTION, ID>>,
+    recording : Option<InputRecording>,
+    replay : Option<InputReplay>,
+    watch_bindings : bool,
+    reload_requested : bool,
+    bindings_watchers : Vec<BindingsWatcher>,
+    reload_result : Option<Result<(), BindingsError>>,
+    overrides_path : Option<PathBuf>,
+    axes_path : Option<PathBuf>,
+    axis_overrides_path : Option<PathBuf>,
+    rebind : Option<RebindTarget<ACTION, ID>>,
+    captured_rebind : Option<CapturedRebind<ACTION, ID>>,
+    rebind_result : Option<Result<RebindOutcome<ACTION>, BindingsError>>,
+    axis_rebind_result : Option<Result<AxisRebindOutcome<ACTION>, BindingsError>>,
+    rebind_keys : Vec<HeldInput>,
+    context_stack : Vec<(ID, u32)>,
+    combos : ComboTracker<ACTION, ID>,
+    combo_events : Vec<ComboEvent<ACTION>>,
+    axes : AxisTracker<ACTION>,
+    axis_events : Vec<AxisEvent<ACTION>>,
+    stepped_axes : bool,
+    text_input : TextInput,
+    text_events : Vec<TextEvent>,
+    text_keys : Vec<KeyCode>,
+    input_state : InputState,
+    metrics : Option<InputMetrics>,
+    /// Capture times of the raw input mapped since the last frame, for the latency metric.
+    captured : Vec<f64>,
+}
+
+impl <ACTION, ID> AmethystEventMapper<ACTION, ID>
+    where ACTION: Hash + Eq + Clone + ActionMetadata + Debug + Serialize + DeserializeOwned,
+          ID: Hash + Eq + Clone + Debug + Serialize + DeserializeOwned {
+
+    /// Creates a mapper for a window of `current_size` pixels. Until a valid size is known from
+    /// here, a resize or `set_window_size`, cursor, motion, scroll and touch positions are dropped.
+    pub fn new(current_size : (f64, f64)) -> AmethystEventMapper<ACTION, ID> {
+        AmethystEventMapper {
+            input_remapper : BindingsReMapper::new(),
+            window_data : WindowData {
+                size : current_size,
+                cursor_position : None
+            },
+            reported_size : None,
+            modifiers : ModifierState::new(),
+            scroll : ScrollState::new(),
+            gamepads : GamepadState::new(),
+            touches : TouchState::new(),
+            gamepad_source : None,
+            players : Vec::default(),
+            recording : None,
+            replay : None,
+            watch_bindings : false,
+            reload_requested : false,
+            bindings_watchers : Vec::default(),
+            reload_result : None,
+            overrides_path : None,
+            axes_path : None,
+            axis_overrides_path : None,
+            rebind : None,
+            captured_rebind : None,
+            rebind_result : None,
+            axis_rebind_result : None,
+            rebind_keys : Vec::default(),
+            context_stack : Vec::default(),
+            combos : ComboTracker::new(),
+            combo_events : Vec::default(),
+            axes : AxisTracker::new(),
+            axis_events : Vec::default(),
+            stepped_axes : false,
+            text_input : TextInput::new(),
+            text_events : Vec::default(),
+            text_keys : Vec::default(),
+            input_state : InputState::new(current_size),
+            metrics : None,
+            captured : Vec::default()
+        }
+    }
+
+    /// Polls `source` for gamepad events on every call to `process`.
+    pub fn with_gamepad_source<S>(&mut self, source : S) -> &mut Self
+        where S: GamepadSource + 'static {
+        self.gamepad_source = Some(Box::new(source));
+        self
+    }
+
+    /// Maps window events to raw input. Window events from glutin 0.8 carry no timestamp, so all
+    /// of them get the time they are mapped at; use `process_timed_events` when the time they
+    /// were received is known. Gamepad input keeps the time from its source.
+    pub fn process_events(&mut self, events : &Vec<WindowEvent>) -> Vec<RawInput> {
+        let t = time::precise_time_s();
+        let timed : Vec<(f64, WindowEvent)> = events.iter().map(|e| (t, e.clone())).collect();
+        self.map_events(&timed, false)
+    }
+
+    /// Maps window events stamped with the time they were received, keeping their timestamps.
+    pub fn process_timed_events(&mut self, events : &[(f64, WindowEvent)]) -> Vec<RawInput> {
+        self.map_events(events, true)
+    }
+
+    /// Maps window events and polls the gamepad source. `captured` tells if the window event
+    /// times are when the events were received, rather than when they were mapped.
+    fn map_events(&mut self, events : &[(f64, WindowEvent)], captured : bool) -> Vec<RawInput> {
+        let started = self.clock();
+        let mut next = self.window_data.clone();
+        let mut raw : Vec<RawInput> = {
+            let reported_size = self.reported_size;
+            let modifiers = &mut self.modifiers;
+            let scroll = &mut self.scroll;
+            let touches = &mut self.touches;
+            events.iter()
+                .flat_map(|&(t, ref e)| process_event(&e.payload, t, reported_size, &mut next,
+                                                      modifiers, scroll, touches))
+                .collect()
+        };
+        self.window_data = next;
+        let (gamepad_events, live) = match self.gamepad_source {
+            Some(ref mut source) => (source.poll(), source.is_live()),
+            None => (Vec::default(), false)
+        };
+        let gamepad_raw = self.process_gamepad_events(&gamepad_events);
+        let finished = self.clock();
+        if let Some(ref mut metrics) = self.metrics {
+            metrics.mapping.record(finished - started);
+            if captured {
+                self.captured.extend(raw.iter().map(|raw| raw.time));
+            }
+            if live {
+                self.captured.extend(gamepad_raw.iter().map(|raw| raw.time));
+            }
+        }
+        raw.extend(gamepad_raw);
+        raw
+    }
+
+    /// Maps gamepad events stamped with the time they happened to raw input, with the pad id
+    /// as device id.
+    pub fn process_gamepad_events(&mut self, events : &[(f64, GamepadEvent)]) -> Vec<RawInput> {
+        let gamepads = &mut self.gamepads;
+        events.iter().flat_map(|&(t, ref e)| gamepads.process_event(e, t)).collect()
+    }
+
+    /// Maps touches stamped with the time they happened to raw input, for touch input that does
+    /// not come from the window.
+    pub fn process_touch_events(&mut self, touches : &[(f64, Touch)]) -> Vec<RawInput> {
+        let size = self.window_data.size;
+        let state = &mut self.touches;
+        touches.iter().flat_map(|&(t, ref touch)| state.process_touch(touch, size, t)).collect()
+    }
+
+    /// Adds a screen region that holds a button down while touched. It must lie within the
+    /// window and must not overlap the regions already added.
+    pub fn with_touch_region(&mut self, region : TouchRegion) -> Result<&mut Self, BindingsError> {
+        self.add_touch_regions(None, vec![region])?;
+        Ok(self)
+    }
+
+    /// Adds every touch region in the RON file at `path`. On error none are added.
+    pub fn load_touch_regions(&mut self, path : &str) -> Result<&mut Self, BindingsError> {
+        let regions = load_touch_regions(path)?;
+        self.add_touch_regions(Some(Path::new(path)), regions)?;
+        Ok(self)
+    }
+
+    fn add_touch_regions(&mut self, path : Option<&Path>, regions : Vec<TouchRegion>) -> Result<(), BindingsError> {
+        self.touches.add_regions(regions).map_err(|problems| BindingsError::Invalid(problems.into_iter()
+            .map(|message| BindingsIssue {
+                path : path.map(|path| path.to_path_buf()).unwrap_or_default(),
+                line : None,
+                context : String::new(),
+                message : message
+            })
+            .collect()))
+    }
+
+    /// Ids of the currently connected gamepads.
+    pub fn connected_gamepads(&self) -> Vec<u32> {
+        self.gamepads.connected()
+    }
+
+    /// Modifier keys currently held down, as attached to key and button input.
+    pub fn modifiers(&self) -> RawInputModifiers {
+        self.modifiers.current()
+    }
+
+    /// Sets the window size positions are normalised by, ignored if it is zero or not finite.
+    pub fn set_window_size(&mut self, size : (f64, f64)) {
+        if is_valid_size(size) {
+            self.window_data.size = size;
+            self.input_state.set_size(size);
+        }
+    }
+
+    /// Tells the mapper the size the window currently reports, like `ScreenDimensions`. Resizes
+    /// are missed while unfocused on some platforms, so when the window regains focus the
+    /// mapper switches to this size and maps the change as a resize. Call it before mapping
+    /// each frame's events.
+    pub fn set_reported_size(&mut self, size : (f64, f64)) {
+        self.reported_size = Some(size);
+    }
+
+    pub fn window_size(&self) -> (f64, f64) {
+        self.window_data.size
+    }
+
+    /// Keys, buttons, cursor, focus, size and frame timing as of the last processed frame.
+    pub fn input_state(&self) -> &InputState {
+        &self.input_state
+    }
+
+    pub fn process(&mut self, events : &Vec<WindowEvent>) -> Vec<remawin::Event<ACTION, ID>> {
+        self.process_players(events).into_iter().map(|(_, event)| event).collect()
+    }
+
+    /// Like `process`, but tags each event with the player it belongs to. Events from the
+    /// shared remapper have no player, events from a player slot carry its index. Window
+    /// events are stamped with the time of this call, the earliest glutin 0.8 lets anyone know
+    /// about them; gamepad input keeps the time from its source.
+    pub fn process_players(&mut self, events : &Vec<WindowEvent>)
+        -> Vec<(Option<usize>, remawin::Event<ACTION, ID>)> {
+        let now = time::precise_time_s();
+        let timed : Vec<(f64, WindowEvent)> = events.iter().map(|e| (now, e.clone())).collect();
+        self.map_players(&timed, now, false)
+    }
+
+    /// Like `process_players`, for events stamped with the time they were received. `now` is
+    /// the time of the frame, used for holds and axes that change between input events.
+    pub fn process_timed_players(&mut self, events : &[(f64, WindowEvent)], now : f64)
+        -> Vec<(Option<usize>, remawin::Event<ACTION, ID>)> {
+        self.map_players(events, now, true)
+    }
+
+    fn map_players(&mut self, events : &[(f64, WindowEvent)], now : f64, captured : bool)
+        -> Vec<(Option<usize>, remawin::Event<ACTION, ID>)> {
+        let changed = self.watch_bindings && self.poll_bindings_watchers();
+        if changed || self.reload_requested {
+            self.reload_requested = false;
+            self.reload_result = Some(self.reload_bindings());
+        }
+        // replays run on the recorded frame times, so timing dependent input plays back the same
+        let replayed = match self.replay {
+            Some(ref mut replay) => Some(replay.frames_until(now)),
+            None => None
+        };
+        match replayed {
+            Some(frames) => {
+                if frames.len() == 0 {
+                    // holds and axes still move on between recorded frames
+                    return self.process_raw_input_at(&Vec::default(), now);
+                }
+                let mut mapped = Vec::default();
+                for frame in frames {
+                    mapped.extend(self.process_raw_input_at(&frame.raw_input, frame.time));
+                }
+                mapped
+            },
+            None => {
+                let raw_input = self.map_events(events, captured);
+                self.process_raw_input_at(&raw_input, now)
+            }
+        }
+    }
+
+    pub fn process_raw_input(&mut self, raw_input : &Vec<RawInput>)
+        -> Vec<(Option<usize>, remawin::Event<ACTION, ID>)> {
+        self.process_raw_input_at(raw_input, time::precise_time_s())
+    }
+
+    /// Maps one frame of raw input, with `now` the time of the frame.
+    pub fn process_raw_input_at(&mut self, raw_input : &Vec<RawInput>, now : f64)
+        -> Vec<(Option<usize>, remawin::Event<ACTION, ID>)> {
+        let started = self.clock();
+        if let Some(ref mut recording) = self.recording {
+            recording.push_frame(now, raw_input.clone());
+        }
+        if let Some(ref mut metrics) = self.metrics {
+            metrics.count_frame(raw_input);
+        }
+        self.input_state.update(raw_input, now);
+        let captured;
+        let raw_input = if self.rebind.is_some() || self.rebind_keys.len() > 0 {
+            captured = self.capture_rebind(raw_input);
+            &captured
+        } else {
+            raw_input
+        };
+        let typed;
+        let raw_input = if self.text_input.is_active() || self.text_keys.len() > 0 {
+            typed = self.filter_text_input(raw_input);
+            &typed
+        } else {
+            raw_input
+        };
+        let active : Vec<ID> = self.context_stack.iter().map(|&(ref id, _)| id.clone()).collect();
+        let completed = self.combos.process(raw_input, &active, now);
+        self.combo_events.extend(completed);
+        self.axes.process(raw_input, None);
+        let remap_started = self.clock();
+        let mut mapped : Vec<(Option<usize>, remawin::Event<ACTION, ID>)> = self.input_remapper
+            .process_raw_input(raw_input)
+            .into_iter()
+            .map(|event| (None, event))
+            .collect();
+        let mut remap_time = self.clock() - remap_started;
+        let timed = self.metrics.is_some();
+        for (index, player) in self.players.iter_mut().enumerate() {
+            let owned : Vec<RawInput> = raw_input.iter()
+                .filter(|raw| player.owns(raw))
+                .cloned()
+                .collect();
+            if owned.len() > 0 {
+                self.axes.process(&owned, Some(index));
+                let remap_started = if timed { time::precise_time_s() } else { 0.0 };
+                mapped.extend(player.remapper_mut()
+                                  .process_raw_input(&owned)
+                                  .into_iter()
+                                  .map(|event| (Some(index), event)));
+                if timed {
+                    remap_time += time::precise_time_s() - remap_started;
+                }
+            }
+        }
+        if !self.stepped_axes {
+            let moved = self.axes.update(now);
+            self.axis_events.extend(moved);
+        }
+        let finished = self.clock();
+        let replaying = self.replay.is_some();
+        // only input with a real capture time counts, which replays and raw input passed in
+        // directly do not have
+        let captured : Vec<f64> = self.captured.drain(..).collect();
+        if let Some(ref mut metrics) = self.metrics {
+            metrics.remapping.record(remap_time);
+            metrics.processing.record(finished - started);
+            if !replaying {
+                for t in captured {
+                    metrics.latency.record(finished - t);
+                }
+            }
+        }
+        self.apply_captured_rebind();
+        mapped
+    }
+
+    /// Starts collecting `InputMetrics`, from zero.
+    pub fn enable_metrics(&mut self) -> &mut Self {
+        self.metrics = Some(InputMetrics::new());
+        self
+    }
+
+    /// Stops collecting metrics, returning what was collected.
+    pub fn disable_metrics(&mut self) -> Option<InputMetrics> {
+        self.metrics.take()
+    }
+
+    pub fn metrics(&self) -> Option<&InputMetrics> {
+        self.metrics.as_ref()
+    }
+
+    /// Current time for metrics, without reading the clock when they are disabled.
+    fn clock(&self) -> f64 {
+        if self.metrics.is_some() {
+            time::precise_time_s()
+        } else {
+            0.0
+        }
+    }
+
+    /// Layers the user overrides file at `path` over the defaults on every load of the bindings.
+    /// Rebinds are saved to this file. Call before `load_bindings`.
+    pub fn with_user_overrides(&mut self, path : &str) -> &mut Self {
+        self.overrides_path = Some(PathBuf::from(path));
+        self
+    }
+
+    /// Validates the bindings file at `path`, with any user overrides layered over it, and
+    /// replaces the contexts of the shared remapper with its contexts. On error nothing
+    /// changes, and the returned error lists every problem found.
+    pub fn load_bindings(&mut self, path : &str) -> Result<&mut Self, BindingsError> {
+        let remapper = self.build_remapper(path.as_ref())?;
+        self.input_remapper.replace_with(remapper);
+        Ok(self)
+    }
+
+    /// Like `load_bindings`, for the remapper of a player slot.
+    pub fn load_player_bindings(&mut self, player : usize, path : &str) -> Result<&mut Self, BindingsError> {
+        if player >= self.players.len() {
+            return Err(BindingsError::UnknownPlayer(player));
+        }
+        let remapper = self.build_remapper(path.as_ref())?;
+        self.players[player].remapper_mut().replace_with(remapper);
+        Ok(self)
+    }
+
+    fn build_remapper(&self, path : &Path) -> Result<BindingsReMapper<ACTION, ID>, BindingsError> {
+        let contexts = load_layered_bindings::<ACTION, ID>(path,
+                                                           self.overrides_path.as_ref().map(|p| p.as_path()))?;
+        BindingsReMapper::from_bindings(path, &contexts)
+    }
+
+    /// Watches every loaded bindings and axes file and the user overrides for changes,
+    /// reloading all bindings and axes when one changes. The outcome of each reload is kept for `take_reload_result`.
+    pub fn watch_bindings(&mut self) -> &mut Self {
+        self.watch_bindings = true;
+        self
+    }
+
+    /// Reloads every bindings and axes file on the next frame, the same way a change to a
+    /// watched file does, whether or not the files are watched.
+    pub fn request_reload(&mut self) -> &mut Self {
+        self.reload_requested = true;
+        self
+    }
+
+    /// Outcome of the last reload of changed bindings files, once.
+    pub fn take_reload_result(&mut self) -> Option<Result<(), BindingsError>> {
+        self.reload_result.take()
+    }
+
+    /// Files the loaded bindings come from.
+    fn bindings_files(&self) -> Vec<PathBuf> {
+        let mut paths : Vec<PathBuf> = Vec::default();
+        let remappers = Some(&self.input_remapper).into_iter()
+            .chain(self.players.iter().map(|p| p.remapper()));
+        let others = vec![&self.overrides_path, &self.axes_path, &self.axis_overrides_path];
+        let others = others.into_iter().filter_map(|p| p.as_ref().map(|p| p.as_path()));
+        for path in remappers.filter_map(|r| r.path()).chain(others) {
+            if !paths.iter().any(|p| p == path) {
+                paths.push(path.to_path_buf());
+            }
+        }
+        paths
+    }
+
+    /// True if any watched file changed since the last poll.
+    fn poll_bindings_watchers(&mut self) -> bool {
+        let paths = self.bindings_files();
+        self.bindings_watchers.retain(|w| paths.iter().any(|p| p == w.path()));
+        for path in paths {
+            if !self.bindings_watchers.iter().any(|w| w.path() == path.as_path()) {
+                self.bindings_watchers.push(BindingsWatcher::new(path));
+            }
+        }
+        // every watcher is polled, so one change is not reported again on the next poll
+        self.bindings_watchers.iter_mut().fold(false, |changed, w| w.poll() || changed)
+    }
+
+    /// Rebuilds the shared remapper and every player remapper from its own bindings file, so
+    /// contexts and mappings removed from a file are gone afterwards, and reloads the axes.
+    /// Active contexts and held state actions are kept. If any file fails to load, nothing is
+    /// changed.
+    pub fn reload_bindings(&mut self) -> Result<(), BindingsError> {
+        let axes = match self.axes_path.clone() {
+            Some(path) => Some(self.build_axes(&path)?),
+            None => None
+        };
+        let shared = match self.input_remapper.path().map(|p| p.to_path_buf()) {
+            Some(path) => Some(self.build_remapper(&path)?),
+            None => None
+        };
+        let mut per_player = Vec::default();
+        for player in self.players.iter() {
+            per_player.push(match player.remapper().path() {
+                Some(path) => Some(self.build_remapper(path)?),
+                None => None
+            });
+        }
+        if let Some(remapper) = shared {
+            self.input_remapper.replace_with(remapper);
+        }
+        for (player, remapper) in self.players.iter_mut().zip(per_player.into_iter()) {
+            if let Some(remapper) = remapper {
+                player.remapper_mut().replace_with(remapper);
+            }
+        }
+        if let Some(axes) = axes {
+            self.axes.replace(axes);
+        }
+        Ok(())
+    }
+
+    /// Adds a chord, sequence or hold combo. Combos with a context only fire while that
+    /// context is on the context stack.
+    pub fn with_combo(&mut self, combo : Combo<ACTION, ID>) -> &mut Self {
+        self.combos.add(combo);
+        self
+    }
+
+    /// Adds every combo in the RON file at `path`.
+    pub fn load_combos(&mut self, path : &str) -> Result<&mut Self, BindingsError> {
+        for combo in load_combos::<ACTION, ID, _>(path)? {
+            self.combos.add(combo);
+        }
+        Ok(self)
+    }
+
+    /// Combos completed since the last call.
+    pub fn drain_combos(&mut self) -> Vec<ComboEvent<ACTION>> {
+        self.combo_events.drain(..).collect()
+    }
+
+    /// Adds a virtual axis, combining keys or a stick into a `Range` action in [-1, 1].
+    pub fn with_axis(&mut self, axis : VirtualAxis<ACTION>) -> &mut Self {
+        self.axes.add(axis);
+        self
+    }
+
+    /// Layers the user axis overrides file at `path` over the axes file on every load of the
+    /// axes. Axis rebinds are saved to this file. Call before `load_axes`.
+    pub fn with_user_axis_overrides(&mut self, path : &str) -> &mut Self {
+        self.axis_overrides_path = Some(PathBuf::from(path));
+        self
+    }
+
+    /// Validates the virtual axes in the RON file at `path`, with any user axis overrides
+    /// layered over it, and replaces every axis with them. Axes of players are checked against
+    /// the player slots, so add the players first. On error nothing changes, and the returned
+    /// error lists every problem found.
+    pub fn load_axes(&mut self, path : &str) -> Result<&mut Self, BindingsError> {
+        let axes = self.build_axes(path.as_ref())?;
+        self.axes.replace(axes);
+        self.axes_path = Some(PathBuf::from(path));
+        Ok(self)
+    }
+
+    fn build_axes(&self, path : &Path) -> Result<Vec<VirtualAxis<ACTION>>, BindingsError> {
+        load_layered_axes::<ACTION>(path,
+                                    self.axis_overrides_path.as_ref().map(|p| p.as_path()),
+                                    self.players.len())
+    }
+
+    /// Leaves moving the axes on to `step_axes`, for games that advance them with a fixed
+    /// simulation step instead of the time between frames. Input still sets what the axes head
+    /// for on every frame.
+    pub fn with_stepped_axes(&mut self) -> &mut Self {
+        self.stepped_axes = true;
+        self
+    }
+
+    /// Advances the axes by `dt` seconds, and returns the axis values that changed. Only
+    /// meaningful after `with_stepped_axes`, otherwise every frame advances them too.
+    pub fn step_axes(&mut self, dt : f32) -> Vec<AxisEvent<ACTION>> {
+        self.axes.advance(dt)
+    }
+
+    /// Axis values that changed since the last call.
+    pub fn drain_axes(&mut self) -> Vec<AxisEvent<ACTION>> {
+        self.axis_events.drain(..).collect()
+    }
+
+    /// Current value of a virtual axis, for a player or shared when `player` is `None`.
+    pub fn axis_value(&self, action : &ACTION, player : Option<usize>) -> f32 {
+        self.axes.value(action, player)
+    }
+
+    /// Starts capture mode: the next key or button press is bound to `action` in `context`
+    /// instead of being mapped, and Escape cancels. The outcome is available from
+    /// `take_rebind_result` once the capture completes.
+    pub fn start_rebind(&mut self, context : ID, action : ACTION) {
+        self.rebind = Some(RebindTarget::Mapping(context, action));
+        self.rebind_result = None;
+    }
+
+    /// Like `start_rebind`, binding the next key press to one direction of the first
+    /// key-driven axis of `action` for `player`. Buttons are ignored. The outcome is available
+    /// from `take_axis_rebind_result`.
+    pub fn start_axis_rebind(&mut self, player : Option<usize>, action : ACTION, direction : AxisDirection) {
+        self.rebind = Some(RebindTarget::AxisKey(player, action, direction));
+        self.axis_rebind_result = None;
+    }
+
+    pub fn cancel_rebind(&mut self) {
+        self.rebind = None;
+    }
+
+    pub fn is_rebinding(&self) -> bool {
+        self.rebind.is_some()
+    }
+
+    pub fn take_rebind_result(&mut self) -> Option<Result<RebindOutcome<ACTION>, BindingsError>> {
+        self.rebind_result.take()
+    }
+
+    pub fn take_axis_rebind_result(&mut self) -> Option<Result<AxisRebindOutcome<ACTION>, BindingsError>> {
+        self.axis_rebind_result.take()
+    }
+
+    /// Binds `raw_type` with `raw_args` to `action` in `context`, swapping with any action that
+    /// used the same input, then saves the user overrides and reloads the bindings.
+    pub fn rebind_action(&mut self,
+                         context : &ID,
+                         action : ACTION,
+                         raw_type : RawType,
+                         raw_args : MappingArgs) -> Result<RebindOutcome<ACTION>, BindingsError> {
+        let (path, overrides) = match (self.input_remapper.path(), &self.overrides_path) {
+            (Some(path), &Some(ref overrides)) => (path.to_path_buf(), overrides.clone()),
+            _ => return Err(BindingsError::NotConfigured(
+                "rebinding needs both a bindings file and a user overrides file".to_string()))
+        };
+        let mapping = Mapping {
+            raw_type : raw_type,
+            raw_args : raw_args,
+            action : action,
+            line : None
+        };
+        let outcome = rebind(&path, &overrides, context, mapping)?;
+        self.reload_bindings()?;
+        Ok(outcome)
+    }
+
+    /// Binds `key` to one direction of the first key-driven axis of `action` for `player`,
+    /// swapping with any axis key that used it, then saves the user axis overrides and reloads.
+    pub fn rebind_axis_key(&mut self,
+                           player : Option<usize>,
+                           action : ACTION,
+                           direction : AxisDirection,
+                           key : KeyCode) -> Result<AxisRebindOutcome<ACTION>, BindingsError> {
+        let (path, overrides) = match (&self.axes_path, &self.axis_overrides_path) {
+            (&Some(ref path), &Some(ref overrides)) => (path.clone(), overrides.clone()),
+            _ => return Err(BindingsError::NotConfigured(
+                "rebinding axes needs both an axes file and a user axis overrides file".to_string()))
+        };
+        let outcome = rebind_axis(&path, &overrides, self.players.len(), player, action, direction, key)?;
+        self.reload_bindings()?;
+        Ok(outcome)
+    }
+
+    /// Saves and reloads the rebind captured this frame, once the frame has been mapped.
+    fn apply_captured_rebind(&mut self) {
+        match self.captured_rebind.take() {
+            Some(CapturedRebind::Mapping(context, action, raw_type, raw_args)) => {
+                let result = self.rebind_action(&context, action, raw_type, raw_args);
+                self.rebind_result = Some(result);
+            },
+            Some(CapturedRebind::AxisKey(player, action, direction, key)) => {
+                let result = self.rebind_axis_key(player, action, direction, key);
+                self.axis_rebind_result = Some(result);
+            },
+            None => ()
+        }
+    }
+
+    /// Looks for the input to bind while in capture mode. The captured input is bound by
+    /// `apply_captured_rebind` after the frame, not while it is mapped. Keys and buttons pressed while
+    /// capturing are held back from the remappers until released, even if the capture
+    /// completes first, while releases of those held before it started go through so no action
+    /// is left stuck.
+    fn capture_rebind(&mut self, raw_input : &Vec<RawInput>) -> Vec<RawInput> {
+        let mut passed = Vec::default();
+        for raw in raw_input {
+            let consumed = match HeldInput::from_raw(raw) {
+                Some((input, RawInputAction::Release, _)) => {
+                    let held = self.rebind_keys.contains(&input);
+                    self.rebind_keys.retain(|k| k != &input);
+                    held
+                },
+                Some((input, RawInputAction::Press, _)) => {
+                    if self.rebind_keys.contains(&input) {
+                        true
+                    } else if self.rebind.is_some() {
+                        self.rebind_keys.push(input);
+                        match capture_input(raw) {
+                            Some(Some((raw_type, raw_args))) => {
+                                let key = raw_args.keycode.clone();
+                                match (self.rebind.take(), key) {
+                                    (Some(RebindTarget::Mapping(context, action)), _) => {
+                                        self.captured_rebind =
+                                            Some(CapturedRebind::Mapping(context, action, raw_type, raw_args));
+                                    },
+                                    (Some(RebindTarget::AxisKey(player, action, direction)), Some(key)) => {
+                                        self.captured_rebind =
+                                            Some(CapturedRebind::AxisKey(player, action, direction, key));
+                                    },
+                                    // axes only take keys, keep waiting for one
+                                    (target, _) => self.rebind = target
+                                }
+                            },
+                            Some(None) => self.rebind = None,
+                            None => ()
+                        };
+                        true
+                    } else {
+                        false
+                    }
+                },
+                // repeats of keys held before the capture are not part of it either
+                Some((input, _, _)) => self.rebind.is_some() || self.rebind_keys.contains(&input),
+                None => {
+                    if let RawInputEvent::Focus(false) = raw.event {
+                        self.rebind_keys.clear();
+                    }
+                    false
+                }
+            };
+            if !consumed {
+                passed.push(raw.clone());
+            }
+        }
+        passed
+    }
+
+    /// Starts text mode: characters, Backspace, Delete, Return, Home, End and the left and
+    /// right arrows edit `buffer`, and key input is not mapped to actions. Return submits the
+    /// line and Escape leaves text mode, both reported as a `TextEvent`.
+    pub fn start_text_input(&mut self, buffer : TextBuffer) {
+        self.text_input.buffer = Some(buffer);
+    }
+
+    /// Leaves text mode, returning the buffer as it was last edited.
+    pub fn stop_text_input(&mut self) -> Option<TextBuffer> {
+        self.text_input.buffer.take()
+    }
+
+    pub fn is_text_input(&self) -> bool {
+        self.text_input.is_active()
+    }
+
+    /// Text mode state, to copy into the world's `TextInput` resource after each frame.
+    pub fn text_input(&self) -> &TextInput {
+        &self.text_input
+    }
+
+    pub fn text_input_mut(&mut self) -> Option<&mut TextBuffer> {
+        self.text_input.buffer.as_mut()
+    }
+
+    /// Lines submitted and text mode cancellations since the last call.
+    pub fn drain_text_events(&mut self) -> Vec<TextEvent> {
+        self.text_events.drain(..).collect()
+    }
+
+    /// Feeds keyboard input to the text buffer while in text mode. Keys pressed in text mode
+    /// are held back from the remappers until released, even if text mode ends first, while
+    /// releases of keys held before it started go through so no action is left stuck.
+    fn filter_text_input(&mut self, raw_input : &Vec<RawInput>) -> Vec<RawInput> {
+        let mut passed = Vec::default();
+        for raw in raw_input {
+            let typing = self.text_input.is_active();
+            let consumed = match raw.event {
+                RawInputEvent::Key(KeyCode::Escape, RawInputAction::Press, _) if typing => {
+                    self.text_keys.push(KeyCode::Escape);
+                    if let Some(buffer) = self.text_input.buffer.take() {
+                        self.text_events.push(TextEvent::Cancelled(buffer));
+                    }
+                    true
+                },
+                RawInputEvent::Key(ref keycode, RawInputAction::Press, _) if typing => {
+                    if !self.text_keys.contains(keycode) {
+                        self.text_keys.push(keycode.clone());
+                    }
+                    true
+                },
+                RawInputEvent::Key(ref keycode, _, _) => {
+                    let held = self.text_keys.contains(keycode);
+                    self.text_keys.retain(|k| k != keycode);
+                    held
+                },
+                RawInputEvent::Char(_) => typing,
+                _ => false
+            };
+            if let Some(ref mut buffer) = self.text_input.buffer {
+                buffer.process(raw);
+                self.text_events.extend(buffer.take_submitted().into_iter().map(TextEvent::Submitted));
+            }
+            if !consumed {
+                passed.push(raw.clone());
+            }
+        }
+        passed
+    }
+
+    /// Starts recording all raw input passed to the remappers, one frame per `process` call.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(InputRecording::new());
+    }
+
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        self.recording.take()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Replaces live window and gamepad input with the frames of `replay`, played back by their
+    /// recorded times from the first `process` call on. Once the replay is exhausted, frames
+    /// are empty until it is stopped.
+    pub fn start_replay(&mut self, replay : InputReplay) {
+        self.replay = Some(replay);
+    }
+
+    pub fn stop_replay(&mut self) -> Option<InputReplay> {
+        self.replay.take()
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    /// Activates `context` on the shared remapper and pushes it on the context stack.
+    /// Contexts with a higher priority take precedence when mapping input.
+    pub fn push_context(&mut self, context : ID, priority : u32) -> &mut Self {
+        self.input_remapper.activate_context(&context, priority);
+        for player in self.players.iter_mut() {
+            player.remapper_mut().activate_context(&context, priority);
+        }
+        self.context_stack.push((context, priority));
+        self
+    }
+
+    /// Like `push_context`, with a priority above every context already on the stack.
+    pub fn push_context_on_top(&mut self, context : ID) -> &mut Self {
+        let priority = self.context_stack.iter().map(|&(_, p)| p + 1).max().unwrap_or(1);
+        self.push_context(context, priority)
+    }
+
+    /// Deactivates and removes the most recently pushed context.
+    pub fn pop_context(&mut self) -> Option<ID> {
+        let popped = self.context_stack.pop();
+        match popped {
+            Some((context, _)) => {
+                self.deactivate_unless_stacked(&context);
+                Some(context)
+            },
+            None => None
+        }
+    }
+
+    /// Removes the most recent push of `context`, wherever it is on the stack.
+    pub fn remove_context(&mut self, context : &ID) -> bool {
+        match self.context_stack.iter().rposition(|&(ref id, _)| id == context) {
+            Some(index) => {
+                self.context_stack.remove(index);
+                self.deactivate_unless_stacked(context);
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Contexts pushed on the shared and player remappers, with their priorities, oldest first.
+    pub fn active_contexts(&self) -> &[(ID, u32)] {
+        &self.context_stack
+    }
+
+    // a context pushed twice stays active, at the priority of its remaining push
+    fn deactivate_unless_stacked(&mut self, context : &ID) {
+        let remaining = self.context_stack.iter()
+            .rev()
+            .find(|&&(ref id, _)| id == context)
+            .map(|&(_, priority)| priority);
+        match remaining {
+            Some(priority) => {
+                self.input_remapper.activate_context(context, priority);
+                for player in self.players.iter_mut() {
+                    player.remapper_mut().activate_context(context, priority);
+                }
+            },
+            None => {
+                self.input_remapper.deactivate_context(context);
+                for player in self.players.iter_mut() {
+                    player.remapper_mut().deactivate_context(context);
+                }
+            }
+        };
+    }
+
+    pub fn remapper_mut(&mut self) -> &mut BindingsReMapper<ACTION, ID> {
+        &mut self.input_remapper
+    }
+
+    /// Adds a player slot and returns its index. The contexts on the context stack are active
+    /// for it as well.
+    pub fn add_player(&mut self) -> usize {
+        let mut player = Player::new();
+        for &(ref context, priority) in &self.context_stack {
+            player.remapper_mut().activate_context(context, priority);
+        }
+        self.players.push(player);
+        self.players.len() - 1
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Remapper holding the bindings of a player slot.
+    pub fn player_remapper_mut(&mut self, player : usize) -> Option<&mut BindingsReMapper<ACTION, ID>> {
+        self.players.get_mut(player).map(|p| p.remapper_mut())
+    }
+
+    pub fn assign_device(&mut self,
+                         player : usize,
+                         device_type : DeviceType,
+                         device_id : u32) -> Result<&mut Self, BindingsError> {
+        match self.players.get_mut(player) {
+            Some(p) => p.assign(device_type, device_id),
+            None => return Err(BindingsError::UnknownPlayer(player))
+        };
+        Ok(self)
+    }
+
+    /// Removes a device from every player it is assigned to.
+    pub fn release_device(&mut self, device_type : &DeviceType, device_id : u32) {
+        for player in self.players.iter_mut() {
+            player.release(device_type, device_id);
+        }
+    }
+
+    /// Players that a device is assigned to.
+    pub fn players_for_device(&self, device_type : &DeviceType, device_id : u32) -> Vec<usize> {
+        self.players.iter()
+            .enumerate()
+            .filter(|&(_, p)| p.owns_device(device_type, device_id))
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+/// What a rebind capture binds the captured input to.
+enum RebindTarget<ACTION, ID> {
+    Mapping(ID, ACTION),
+    AxisKey(Option<usize>, ACTION, AxisDirection)
+}
+
+/// Input captured for a rebind, waiting to be saved once the frame is mapped.
+enum CapturedRebind<ACTION, ID> {
+    Mapping(ID, ACTION, RawType, MappingArgs),
+    AxisKey(Option<usize>, ACTION, AxisDirection, KeyCode)
+}
+
+/// Input to bind for a key or button press, or `Some(None)` when Escape cancels the capture.
+/// Modifiers held with a key are required by the new binding, unless the key is a modifier.
+fn capture_input(raw : &RawInput) -> Option<Option<(RawType, MappingArgs)>> {
+    match raw.event {
+        RawInputEvent::Key(KeyCode::Escape, RawInputAction::Press, _) => Some(None),
+        RawInputEvent::Key(ref keycode, RawInputAction::Press, modifiers) => {
+            let mut args = MappingArgs::default();
+            args.keycode = Some(keycode.clone());
+            if !is_modifier_key(keycode) {
+                args.modifiers = Modifier::from_bits(modifiers);
+            }
+            Some(Some((RawType::Key, args)))
+        },
+        RawInputEvent::Button(button, _, RawInputAction::Press, modifiers) => {
+            let mut args = MappingArgs::default();
+            args.button = Some(button);
+            args.device_type = Some(raw.device_type.clone());
+            args.modifiers = Modifier::from_bits(modifiers);
+            Some(Some((RawType::Button, args)))
+        },
+        _ => None
+    }
+}
+
+fn is_modifier_key(keycode : &KeyCode) -> bool {
+    match *keycode {
+        KeyCode::LShift | KeyCode::RShift | KeyCode::LControl | KeyCode::RControl |
+        KeyCode::LAlt | KeyCode::RAlt | KeyCode::LMenu | KeyCode::RMenu |
+        KeyCode::LWin | KeyCode::RWin => true,
+        _ => false
+    }
+}
+
+/// Tracks held modifier keys from key press/release events, since the window events do not
+/// carry modifier state. Left and right keys are tracked separately, so releasing one Shift
+/// while the other is held keeps SHIFT set.
+#[derive(Clone, Debug)]
+struct ModifierState {
+    left : RawInputModifiers,
+    right : RawInputModifiers
+}
+
+impl ModifierState {
+    fn new() -> ModifierState {
+        ModifierState {
+            left : RawInputModifiers::empty(),
+            right : RawInputModifiers::empty()
+        }
+    }
+
+    fn current(&self) -> RawInputModifiers {
+        self.left | self.right
+    }
+
+    fn update(&mut self, keycode : &Option<amethyst::VirtualKeyCode>, state : &amethyst::ElementState) {
+        let pressed = *state == amethyst::ElementState::Pressed;
+        match *keycode {
+            Some(amethyst::VirtualKeyCode::LShift) => self.left.set(remawin::raw::SHIFT, pressed),
+            Some(amethyst::VirtualKeyCode::RShift) => self.right.set(remawin::raw::SHIFT, pressed),
+            Some(amethyst::VirtualKeyCode::LControl) => self.left.set(remawin::raw::CONTROL, pressed),
+            Some(amethyst::VirtualKeyCode::RControl) => self.right.set(remawin::raw::CONTROL, pressed),
+            Some(amethyst::VirtualKeyCode::LAlt) |
+            Some(amethyst::VirtualKeyCode::LMenu) => self.left.set(remawin::raw::ALT, pressed),
+            Some(amethyst::VirtualKeyCode::RAlt) |
+            Some(amethyst::VirtualKeyCode::RMenu) => self.right.set(remawin::raw::ALT, pressed),
+            Some(amethyst::VirtualKeyCode::LWin) => self.left.set(remawin::raw::SUPER, pressed),
+            Some(amethyst::VirtualKeyCode::RWin) => self.right.set(remawin::raw::SUPER, pressed),
+            _ => ()
+        }
+    }
+
+    fn clear(&mut self) {
+        self.left = RawInputModifiers::empty();
+        self.right = RawInputModifiers::empty();
+    }
+}
+
+/// Pixels scrolled per line for wheels that report line deltas.
+const SCROLL_LINE_HEIGHT : f64 = 20.0;
+
+/// Mouse buttons reported for each whole wheel step, following the X11 convention.
+pub const WHEEL_UP_BUTTON : u32 = 4;
+pub const WHEEL_DOWN_BUTTON : u32 = 5;
+pub const WHEEL_LEFT_BUTTON : u32 = 6;
+pub const WHEEL_RIGHT_BUTTON : u32 = 7;
+
+/// Accumulates partial wheel steps, so that touchpads reporting small pixel deltas still
+/// produce discrete steps once a full line has been scrolled.
+#[derive(Clone, Debug)]
+struct ScrollState {
+    remainder : (f64, f64)
+}
+
+impl ScrollState {
+    fn new() -> ScrollState {
+        ScrollState {
+            remainder : (0.0, 0.0)
+        }
+    }
+
+    /// Adds a delta in lines, and returns the number of whole steps taken on each axis.
+    fn accumulate(&mut self, lines : (f64, f64)) -> (i32, i32) {
+        let x = self.remainder.0 + lines.0;
+        let y = self.remainder.1 + lines.1;
+        let steps = (x.trunc(), y.trunc());
+        self.remainder = (x - steps.0, y - steps.1);
+        (steps.0 as i32, steps.1 as i32)
+    }
+}
+
+fn process_event(event : &Event,
+                 t : f64,
+                 reported_size : Option<(f64, f64)>,
+                 next: &mut WindowData,
+                 modifiers: &mut ModifierState,
+                 scroll: &mut ScrollState,
+                 touches: &mut TouchState) -> Vec<RawInput> {
+    match event {
+        &Event::Closed => {
+            vec![RawInput::new(t, DeviceType::Window, 0, RawInputEvent::Close)]
+        },
+        &Event::Resized(x, y) => {
+            // keep normalising by the last usable size while minimised
+            if is_valid_size((x as f64, y as f64)) {
+                next.size = (x as f64, y as f64);
+            }
+            vec![RawInput::new(t, DeviceType::Window, 0,
+                               RawInputEvent::Resize(x as u32, y as u32))]
+        },
+        &Event::Focused(b) => {
+            // releases that happen while unfocused are never delivered
+            let mut raw = Vec::default();
+            if !b {
+                modifiers.clear();
+                raw.extend(touches.release_all(t));
+            }
+            raw.push(RawInput::new(t, DeviceType::Window, 0,
+                                   RawInputEvent::Focus(b)));
+            // the window may have been resized while it was in the background
+            if let Some(size) = reported_size {
+                if b && is_valid_size(size) && size != next.size {
+                    next.size = size;
+                    raw.push(RawInput::new(t, DeviceType::Window, 0,
+                                           RawInputEvent::Resize(size.0 as u32, size.1 as u32)));
+                }
+            }
+            raw
+        },
+        &Event::ReceivedCharacter(ch) => {
+            vec![RawInput::new(t, DeviceType::Keyboard, 0,
+                               RawInputEvent::Char(ch))]
+        },
+        &Event::KeyboardInput(state, _ , virtual_keycode) => {
+            modifiers.update(&virtual_keycode, &state);
+            vec![RawInput::new(t, DeviceType::Keyboard, 0,
+                               RawInputEvent::Key(map_keycode(&virtual_keycode),
+                                                  map_action(&state),
+                                                  modifiers.current()))]
+        },
+        &Event::MouseInput(state, button) => {
+            vec![RawInput::new(t, DeviceType::Mouse, 0,
+                               RawInputEvent::Button(map_mouse_button(&button),
+                                                     match next.cursor_position {
+                                                         Some(position) => position,
+                                                         None => (0.0, 0.0)
+                                                     },
+                                                     map_action(&state),
+                                                     modifiers.current()))]
+        },
+        &Event::MouseMoved(x, y) => {
+            let mut raw = Vec::new();
+            let (x, y) = (x as f64, y as f64);
+            // positions outside the window happen while dragging, and are clamped to its edges
+            if let Some((nx, ny)) = normalise((x, y), next.size) {
+                raw.push(RawInput::new(t, DeviceType::Mouse, 0,
+                                       RawInputEvent::CursorPosition(nx.max(0.0).min(1.0),
+                                                                     ny.max(0.0).min(1.0))));
+            }
+            let motion = next.cursor_position.and_then(|(px, py)| normalise((x - px, y - py), next.size));
+            if let Some((dx, dy)) = motion {
+                raw.push(RawInput::new(t, DeviceType::Mouse, 0, RawInputEvent::Motion(dx, dy)));
+            }
+            next.cursor_position = Some((x, y));
+            raw
+        },
+        &Event::Touch(ref touch) => {
+            touches.process_touch(touch, next.size, t)
+        },
+        &Event::MouseWheel(delta, _) => {
+            let (px, py) = match delta {
+                MouseScrollDelta::LineDelta(x, y) =>
+                    (x as f64 * SCROLL_LINE_HEIGHT, y as f64 * SCROLL_LINE_HEIGHT),
+                MouseScrollDelta::PixelDelta(x, y) => (x as f64, y as f64)
+            };
+            let mut raw = Vec::new();
+            if let Some((sx, sy)) = normalise((px, py), next.size) {
+                raw.push(RawInput::new(t, DeviceType::Mouse, 0, RawInputEvent::Scroll(sx, sy)));
+            }
+            let (steps_x, steps_y) = scroll.accumulate((px / SCROLL_LINE_HEIGHT,
+                                                        py / SCROLL_LINE_HEIGHT));
+            let position = match next.cursor_position {
+                Some(position) => position,
+                None => (0.0, 0.0)
+            };
+            let steps = [(steps_x, WHEEL_RIGHT_BUTTON, WHEEL_LEFT_BUTTON),
+                         (steps_y, WHEEL_UP_BUTTON, WHEEL_DOWN_BUTTON)];
+            for &(count, positive, negative) in steps.iter() {
+                let button = if count > 0 { positive } else { negative };
+                for _ in 0..count.abs() {
+                    for action in &[RawInputAction::Press, RawInputAction::Release] {
+                        raw.push(RawInput::new(t, DeviceType::Mouse, 0,
+                                               RawInputEvent::Button(button,
+                                                                     position,
+                                                                     action.clone(),
+                                                                     modifiers.current())));
+                    }
+                }
+            }
+            raw
+        },
+        _ => Vec::default()
+    }
+}
+
+fn map_action(element_state: &amethyst::ElementState) -> RawInputAction {
+    match element_state{
+        &amethyst::ElementState::Pressed => RawInputAction::Press,
+        &amethyst::ElementState::Released => RawInputAction::Release,
+    }
+}
+
+fn map_mouse_button(button: &amethyst::MouseButton) -> u32 {
+    match button {
+        &amethyst::MouseButton::Left => 1,
+        &amethyst::MouseButton::Right => 2,
+        &amethyst::MouseButton::Middle => 3,
+        &amethyst::MouseButton::Other(b) => b as u32,
+    }
+}
+
+fn map_keycode(keycode: &Option<amethyst::VirtualKeyCode>) -> KeyCode {
+    match *keycode {
+        Some(kc) => {
+            match kc {
+                amethyst::VirtualKeyCode::Key1 => KeyCode::Key1,
+                amethyst::VirtualKeyCode::Key2 => KeyCode::Key2,
+                amethyst::VirtualKeyCode::Key3 => KeyCode::Key3,
+                amethyst::VirtualKeyCode::Key4 => KeyCode::Key4,
+                amethyst::VirtualKeyCode::Key5 => KeyCode::Key5,
+                amethyst::VirtualKeyCode::Key6 => KeyCode::Key6,
+                amethyst::VirtualKeyCode::Key7 => KeyCode::Key7,
+                amethyst::VirtualKeyCode::Key8 => KeyCode::Key8,
+                amethyst::VirtualKeyCode::Key9 => KeyCode::Key9,
+                amethyst::VirtualKeyCode::Key0 => KeyCode::Key0,
+                amethyst::VirtualKeyCode::A => KeyCode::A,
+                amethyst::VirtualKeyCode::B => KeyCode::B,
+                amethyst::VirtualKeyCode::C => KeyCode::C,
+                amethyst::VirtualKeyCode::D => KeyCode::D,
+                amethyst::VirtualKeyCode::E => KeyCode::E,
+                amethyst::VirtualKeyCode::F => KeyCode::F,
+                amethyst::VirtualKeyCode::G => KeyCode::G,
+                amethyst::VirtualKeyCode::H => KeyCode::H,
+                amethyst::VirtualKeyCode::I => KeyCode::I,
+                amethyst::VirtualKeyCode::J => KeyCode::J,
+                amethyst::VirtualKeyCode::K => KeyCode::K,
+                amethyst::VirtualKeyCode::L => KeyCode::L,
+                amethyst::VirtualKeyCode::M => KeyCode::M,
+                amethyst::VirtualKeyCode::N => KeyCode::N,
+                amethyst::VirtualKeyCode::O => KeyCode::O,
+                amethyst::VirtualKeyCode::P => KeyCode::P,
+                amethyst::VirtualKeyCode::Q => KeyCode::Q,
+                amethyst::VirtualKeyCode::R => KeyCode::R,
+                amethyst::VirtualKeyCode::S => KeyCode::S,
+                amethyst::VirtualKeyCode::T => KeyCode::T,
+                amethyst::VirtualKeyCode::U => KeyCode::U,
+                amethyst::VirtualKeyCode::V => KeyCode::V,
+                amethyst::VirtualKeyCode::W => KeyCode::W,
+                amethyst::VirtualKeyCode::X => KeyCode::X,
+                amethyst::VirtualKeyCode::Y => KeyCode::Y,
+                amethyst::VirtualKeyCode::Z => KeyCode::Z,
+                amethyst::VirtualKeyCode::Escape => KeyCode::Escape,
+                amethyst::VirtualKeyCode::F1 => KeyCode::F1,
+                amethyst::VirtualKeyCode::F2 => KeyCode::F2,
+                amethyst::VirtualKeyCode::F3 => KeyCode::F3,
+                amethyst::VirtualKeyCode::F4 => KeyCode::F4,
+                amethyst::VirtualKeyCode::F5 => KeyCode::F5,
+                amethyst::VirtualKeyCode::F6 => KeyCode::F6,
+                amethyst::VirtualKeyCode::F7 => KeyCode::F7,
+                amethyst::VirtualKeyCode::F8 => KeyCode::F8,
+                amethyst::VirtualKeyCode::F9 => KeyCode::F9,
+                amethyst::VirtualKeyCode::F10 => KeyCode::F10,
+                amethyst::VirtualKeyCode::F11 => KeyCode::F11,
+                amethyst::VirtualKeyCode::F12 => KeyCode::F12,
+                amethyst::VirtualKeyCode::F13 => KeyCode::F13,
+                amethyst::VirtualKeyCode::F14 => KeyCode::F14,
+                amethyst::VirtualKeyCode::F15 => KeyCode::F15,
+                amethyst::VirtualKeyCode::Snapshot => KeyCode::Snapshot,
+                amethyst::VirtualKeyCode::Scroll => KeyCode::Scroll,
+                amethyst::VirtualKeyCode::Pause => KeyCode::Pause,
+                amethyst::VirtualKeyCode::Insert => KeyCode::Insert,
+                amethyst::VirtualKeyCode::Home => KeyCode::Home,
+                amethyst::VirtualKeyCode::Delete => KeyCode::Delete,
+                amethyst::VirtualKeyCode::End => KeyCode::End,
+                amethyst::VirtualKeyCode::PageDown => KeyCode::PageDown,
+                amethyst::VirtualKeyCode::PageUp => KeyCode::PageUp,
+                amethyst::VirtualKeyCode::Left => KeyCode::Left,
+                amethyst::VirtualKeyCode::Up => KeyCode::Up,
+                amethyst::VirtualKeyCode::Right => KeyCode::Right,
+                amethyst::VirtualKeyCode::Down => KeyCode::Down,
+                amethyst::VirtualKeyCode::Back => KeyCode::Back,
+                amethyst::VirtualKeyCode::Return => KeyCode::Return,
+                amethyst::VirtualKeyCode::Space => KeyCode::Space,
+                amethyst::VirtualKeyCode::Compose => KeyCode::Compose,
+                amethyst::VirtualKeyCode::Numlock => KeyCode::Numlock,
+                amethyst::VirtualKeyCode::Numpad0 => KeyCode::Numpad0,
+                amethyst::VirtualKeyCode::Numpad1 => KeyCode::Numpad1,
+                amethyst::VirtualKeyCode::Numpad2 => KeyCode::Numpad2,
+                amethyst::VirtualKeyCode::Numpad3 => KeyCode::Numpad3,
+                amethyst::VirtualKeyCode::Numpad4 => KeyCode::Numpad4,
+                amethyst::VirtualKeyCode::Numpad5 => KeyCode::Numpad5,
+                amethyst::VirtualKeyCode::Numpad6 => KeyCode::Numpad6,
+                amethyst::VirtualKeyCode::Numpad7 => KeyCode::Numpad7,
+                amethyst::VirtualKeyCode::Numpad8 => KeyCode::Numpad8,
+                amethyst::VirtualKeyCode::Numpad9 => KeyCode::Numpad9,
+                amethyst::VirtualKeyCode::AbntC1 => KeyCode::AbntC1,
+                amethyst::VirtualKeyCode::AbntC2 => KeyCode::AbntC2,
+                amethyst::VirtualKeyCode::Add => KeyCode::Add,
+                amethyst::VirtualKeyCode::Apostrophe => KeyCode::Apostrophe,
+                amethyst::VirtualKeyCode::Apps => KeyCode::Apps,
+                amethyst::VirtualKeyCode::At => KeyCode::At,
+                amethyst::VirtualKeyCode::Ax => KeyCode::Ax,
+                amethyst::VirtualKeyCode::Backslash => KeyCode::Backslash,
+                amethyst::VirtualKeyCode::Calculator => KeyCode::Calculator,
+                amethyst::VirtualKeyCode::Capital => KeyCode::Capital,
+                amethyst::VirtualKeyCode::Colon => KeyCode::Colon,
+                amethyst::VirtualKeyCode::Comma => KeyCode::Comma,
+                amethyst::VirtualKeyCode::Convert => KeyCode::Convert,
+                amethyst::VirtualKeyCode::Decimal => KeyCode::Decimal,
+                amethyst::VirtualKeyCode::Divide => KeyCode::Divide,
+                amethyst::VirtualKeyCode::Equals => KeyCode::Equals,
+                amethyst::VirtualKeyCode::Grave => KeyCode::Grave,
+                amethyst::VirtualKeyCode::Kana => KeyCode::Kana,
+                amethyst::VirtualKeyCode::Kanji => KeyCode::Kanji,
+                amethyst::VirtualKeyCode::LAlt => KeyCode::LAlt,
+                amethyst::VirtualKeyCode::LBracket => KeyCode::LBracket,
+                amethyst::VirtualKeyCode::LControl => KeyCode::LControl,
+                amethyst::VirtualKeyCode::LMenu => KeyCode::LMenu,
+                amethyst::VirtualKeyCode::LShift => KeyCode::LShift,
+                amethyst::VirtualKeyCode::LWin => KeyCode::LWin,
+                amethyst::VirtualKeyCode::Mail => KeyCode::Mail,
+                amethyst::VirtualKeyCode::MediaSelect => KeyCode::MediaSelect,
+                amethyst::VirtualKeyCode::MediaStop => KeyCode::MediaStop,
+                amethyst::VirtualKeyCode::Minus => KeyCode::Minus,
+                amethyst::VirtualKeyCode::Multiply => KeyCode::Multiply,
+                amethyst::VirtualKeyCode::Mute => KeyCode::Mute,
+                amethyst::VirtualKeyCode::MyComputer => KeyCode::MyComputer,
+                amethyst::VirtualKeyCode::NavigateForward => KeyCode::NavigateForward,
+                amethyst::VirtualKeyCode::NavigateBackward => KeyCode::NavigateBackward,
+                amethyst::VirtualKeyCode::NextTrack => KeyCode::NextTrack,
+                amethyst::VirtualKeyCode::NoConvert => KeyCode::NoConvert,
+                amethyst::VirtualKeyCode::NumpadComma => KeyCode::NumpadComma,
+                amethyst::VirtualKeyCode::NumpadEnter => KeyCode::NumpadEnter,
+                amethyst::VirtualKeyCode::NumpadEquals => KeyCode::NumpadEquals,
+                amethyst::VirtualKeyCode::OEM102 => KeyCode::OEM102,
+                amethyst::VirtualKeyCode::Period => KeyCode::Period,
+                amethyst::VirtualKeyCode::PlayPause => KeyCode::PlayPause,
+                amethyst::VirtualKeyCode::Power => KeyCode::Power,
+                amethyst::VirtualKeyCode::PrevTrack => KeyCode::PrevTrack,
+                amethyst::VirtualKeyCode::RAlt => KeyCode::RAlt,
+                amethyst::VirtualKeyCode::RBracket => KeyCode::RBracket,
+                amethyst::VirtualKeyCode::RControl => KeyCode::RControl,
+                amethyst::VirtualKeyCode::RMenu => KeyCode::RMenu,
+                amethyst::VirtualKeyCode::RShift => KeyCode::RShift,
+                amethyst::VirtualKeyCode::RWin => KeyCode::RWin,
+                amethyst::VirtualKeyCode::Semicolon => KeyCode::Semicolon,
+                amethyst::VirtualKeyCode::Slash => KeyCode::Slash,
+                amethyst::VirtualKeyCode::Sleep => KeyCode::Sleep,
+                amethyst::VirtualKeyCode::Stop => KeyCode::Stop,
+                amethyst::VirtualKeyCode::Subtract => KeyCode::Subtract,
+                amethyst::VirtualKeyCode::Sysrq => KeyCode::Sysrq,
+                amethyst::VirtualKeyCode::Tab => KeyCode::Tab,
+                amethyst::VirtualKeyCode::Underline => KeyCode::Underline,
+                amethyst::VirtualKeyCode::Unlabeled => KeyCode::Unlabeled,
+                amethyst::VirtualKeyCode::VolumeDown => KeyCode::VolumeDown,
+                amethyst::VirtualKeyCode::VolumeUp => KeyCode::VolumeUp,
+                amethyst::VirtualKeyCode::Wake => KeyCode::Wake,
+                amethyst::VirtualKeyCode::WebBack => KeyCode::WebBack,
+                amethyst::VirtualKeyCode::WebFavorites => KeyCode::WebFavorites,
+                amethyst::VirtualKeyCode::WebForward => KeyCode::WebForward,
+                amethyst::VirtualKeyCode::WebHome => KeyCode::WebHome,
+                amethyst::VirtualKeyCode::WebRefresh => KeyCode::WebRefresh,
+                amethyst::VirtualKeyCode::WebSearch => KeyCode::WebSearch,
+                amethyst::VirtualKeyCode::WebStop => KeyCode::WebStop,
+                amethyst::VirtualKeyCode::Yen => KeyCode::Yen,
+            }
+        },
+        None => KeyCode::None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amethyst::{ElementState, VirtualKeyCode};
+    use glutin::TouchPhase;
+    use remawin::raw;
+    use touch::TOUCH_BUTTON;
+    use test_util::TempFile;
+
+    /// The state `process_event` keeps between window events, for a window of 800x600.
+    struct EventState {
+        window : WindowData,
+        reported_size : Option<(f64, f64)>,
+        modifiers : ModifierState,
+        scroll : ScrollState,
+        touches : TouchState
+    }
+
+    impl EventState {
+        fn new() -> EventState {
+            EventState {
+                window : WindowData {
+                    size : (800.0, 600.0),
+                    cursor_position : None
+                },
+                reported_size : None,
+                modifiers : ModifierState::new(),
+                scroll : ScrollState::new(),
+                touches : TouchState::new()
+            }
+        }
+
+        fn process(&mut self, event : Event) -> Vec<RawInput> {
+            process_event(&event, 1.0, self.reported_size, &mut self.window,
+                          &mut self.modifiers, &mut self.scroll, &mut self.touches)
+        }
+
+        fn key(&mut self, state : ElementState, keycode : VirtualKeyCode) -> Vec<RawInput> {
+            self.process(Event::KeyboardInput(state, 0, Some(keycode)))
+        }
+    }
+
+    /// Modifiers of the key events in `raw_input`.
+    fn key_modifiers(raw_input : &[RawInput]) -> Vec<RawInputModifiers> {
+        raw_input.iter()
+            .filter_map(|raw| match raw.event {
+                RawInputEvent::Key(_, _, modifiers) => Some(modifiers),
+                _ => None
+            })
+            .collect()
+    }
+
+    /// Button and action of mouse button input.
+    fn mouse_buttons(raw_input : &[RawInput]) -> Vec<(u32, RawInputAction)> {
+        raw_input.iter()
+            .filter_map(|raw| match raw.event {
+                RawInputEvent::Button(button, _, ref action, _) => Some((button, action.clone())),
+                _ => None
+            })
+            .collect()
+    }
+
+    fn scrolled(raw_input : &[RawInput]) -> Vec<(f64, f64)> {
+        raw_input.iter()
+            .filter_map(|raw| match raw.event {
+                RawInputEvent::Scroll(x, y) => Some((x, y)),
+                _ => None
+            })
+            .collect()
+    }
+
+    #[test]
+    fn left_and_right_modifiers_are_tracked_separately() {
+        let mut state = EventState::new();
+        state.key(ElementState::Pressed, VirtualKeyCode::LShift);
+        state.key(ElementState::Pressed, VirtualKeyCode::RShift);
+        state.key(ElementState::Released, VirtualKeyCode::LShift);
+        assert_eq!(key_modifiers(&state.key(ElementState::Pressed, VirtualKeyCode::S)), vec![raw::SHIFT]);
+        state.key(ElementState::Released, VirtualKeyCode::RShift);
+        assert_eq!(key_modifiers(&state.key(ElementState::Pressed, VirtualKeyCode::S)),
+                   vec![RawInputModifiers::empty()]);
+
+        state.key(ElementState::Pressed, VirtualKeyCode::LControl);
+        state.key(ElementState::Pressed, VirtualKeyCode::RAlt);
+        assert_eq!(key_modifiers(&state.key(ElementState::Pressed, VirtualKeyCode::S)),
+                   vec![raw::CONTROL | raw::ALT]);
+    }
+
+    #[test]
+    fn losing_focus_clears_modifiers() {
+        let mut state = EventState::new();
+        state.key(ElementState::Pressed, VirtualKeyCode::LControl);
+        state.key(ElementState::Pressed, VirtualKeyCode::RShift);
+        state.process(Event::Focused(false));
+        state.process(Event::Focused(true));
+        assert_eq!(key_modifiers(&state.key(ElementState::Pressed, VirtualKeyCode::S)),
+                   vec![RawInputModifiers::empty()]);
+    }
+
+    #[test]
+    fn losing_focus_lifts_fingers_before_reporting_it() {
+        let mut state = EventState::new();
+        state.process(Event::Touch(Touch { phase : TouchPhase::Started, location : (400.0, 300.0), id : 0 }));
+        let raw = state.process(Event::Focused(false));
+        assert_eq!(mouse_buttons(&raw), vec![(TOUCH_BUTTON, RawInputAction::Release)]);
+        match raw.last().map(|raw| &raw.event) {
+            Some(&RawInputEvent::Focus(false)) => (),
+            other => panic!("expected focus loss last, got {:?}", other)
+        };
+        assert_eq!(state.process(Event::Focused(false)).len(), 1);
+    }
+
+    #[test]
+    fn touch_regions_are_checked_when_added() {
+        let mut mapper = TestMapper::new((800.0, 600.0));
+        let left = TouchRegion { button : 122, left : 0.0, top : 0.0, right : 0.5, bottom : 1.0 };
+        assert!(mapper.with_touch_region(left.clone()).is_ok());
+        let err = match mapper.with_touch_region(TouchRegion { button : 123, .. left }) {
+            Err(err) => err,
+            Ok(_) => panic!("overlapping touch regions were added")
+        };
+        assert_eq!(err.to_string(), "touch region of button 123: overlaps the region of button 122\n");
+    }
+
+    #[test]
+    fn regaining_focus_switches_to_the_reported_size() {
+        let mut state = EventState::new();
+        state.reported_size = Some((1024.0, 768.0));
+        let raw = state.process(Event::Focused(false));
+        assert_eq!(state.window.size, (800.0, 600.0));
+        assert_eq!(raw.len(), 1);
+
+        let raw = state.process(Event::Focused(true));
+        assert_eq!(state.window.size, (1024.0, 768.0));
+        match raw.last().map(|raw| &raw.event) {
+            Some(&RawInputEvent::Resize(1024, 768)) => (),
+            other => panic!("expected a resize, got {:?}", other)
+        };
+        // nothing changed since
+        assert_eq!(state.process(Event::Focused(true)).len(), 1);
+    }
+
+    #[test]
+    fn minimised_sizes_are_not_used() {
+        let mut state = EventState::new();
+        state.process(Event::Resized(0, 0));
+        assert_eq!(state.window.size, (800.0, 600.0));
+        state.reported_size = Some((0.0, 0.0));
+        assert_eq!(state.process(Event::Focused(true)).len(), 1);
+        assert_eq!(state.window.size, (800.0, 600.0));
+
+        let mut mapper = TestMapper::new((0.0, 0.0));
+        mapper.set_window_size((0.0, 600.0));
+        assert_eq!(mapper.window_size(), (0.0, 0.0));
+
+        // positions cannot be normalised until a usable size is known
+        state.window.size = (0.0, 0.0);
+        assert!(state.process(Event::MouseMoved(10, 10)).is_empty());
+        state.process(Event::Resized(100, 100));
+        match state.process(Event::MouseMoved(10, 10)).first().map(|raw| &raw.event) {
+            Some(&RawInputEvent::CursorPosition(x, y)) => assert_eq!((x, y), (0.1, 0.1)),
+            other => panic!("expected a cursor position, got {:?}", other)
+        };
+    }
+
+    #[test]
+    fn line_deltas_scroll_by_the_line_height() {
+        let mut state = EventState::new();
+        let raw = state.process(Event::MouseWheel(MouseScrollDelta::LineDelta(0.0, 1.0), TouchPhase::Moved));
+        assert_eq!(scrolled(&raw), vec![(0.0, SCROLL_LINE_HEIGHT / 600.0)]);
+        assert_eq!(mouse_buttons(&raw), vec![(WHEEL_UP_BUTTON, RawInputAction::Press),
+                                             (WHEEL_UP_BUTTON, RawInputAction::Release)]);
+    }
+
+    #[test]
+    fn every_whole_line_presses_and_releases_a_wheel_button() {
+        let mut state = EventState::new();
+        let raw = state.process(Event::MouseWheel(MouseScrollDelta::LineDelta(-2.0, -1.0), TouchPhase::Moved));
+        assert_eq!(mouse_buttons(&raw), vec![(WHEEL_LEFT_BUTTON, RawInputAction::Press),
+                                             (WHEEL_LEFT_BUTTON, RawInputAction::Release),
+                                             (WHEEL_LEFT_BUTTON, RawInputAction::Press),
+                                             (WHEEL_LEFT_BUTTON, RawInputAction::Release),
+                                             (WHEEL_DOWN_BUTTON, RawInputAction::Press),
+                                             (WHEEL_DOWN_BUTTON, RawInputAction::Release)]);
+        let raw = state.process(Event::MouseWheel(MouseScrollDelta::LineDelta(1.0, 0.0), TouchPhase::Moved));
+        assert_eq!(mouse_buttons(&raw), vec![(WHEEL_RIGHT_BUTTON, RawInputAction::Press),
+                                             (WHEEL_RIGHT_BUTTON, RawInputAction::Release)]);
+    }
+
+    #[test]
+    fn partial_pixel_deltas_add_up_to_steps() {
+        // 8 pixels at a time, 20 to a line
+        let mut state = EventState::new();
+        let wheel = |pixels| Event::MouseWheel(MouseScrollDelta::PixelDelta(0.0, pixels), TouchPhase::Moved);
+        let first = state.process(wheel(8.0));
+        assert_eq!(scrolled(&first), vec![(0.0, 8.0 / 600.0)]);
+        assert!(mouse_buttons(&first).is_empty());
+        assert!(mouse_buttons(&state.process(wheel(8.0))).is_empty());
+        assert_eq!(mouse_buttons(&state.process(wheel(8.0))),
+                   vec![(WHEEL_UP_BUTTON, RawInputAction::Press), (WHEEL_UP_BUTTON, RawInputAction::Release)]);
+        // the 4 pixels left over carry into the next line
+        assert!(mouse_buttons(&state.process(wheel(8.0))).is_empty());
+        assert_eq!(mouse_buttons(&state.process(wheel(12.0))).len(), 2);
+    }
+
+    #[test]
+    fn scroll_steps_in_opposite_directions_cancel_out() {
+        let mut scroll = ScrollState::new();
+        assert_eq!(scroll.accumulate((0.0, 0.6)), (0, 0));
+        assert_eq!(scroll.accumulate((0.0, -0.5)), (0, 0));
+        assert_eq!(scroll.accumulate((0.0, 1.0)), (0, 1));
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    enum TestAction {
+        Jump,
+        Land,
+        Duck
+    }
+
+    impl ActionMetadata for TestAction {
+        fn mapped_type(&self) -> remawin::MappedType {
+            remawin::MappedType::Action
+        }
+
+        fn args(&self) -> Vec<remawin::ActionArgument> {
+            Vec::default()
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    enum TestContext {
+        Default,
+        Menu,
+        Pause
+    }
+
+    type TestMapper = AmethystEventMapper<TestAction, TestContext>;
+
+    /// Pressing W jumps and releasing it lands.
+    const JUMP_ON_W : &str = "[
+        Context(
+            id: Default,
+            mappings: [
+                (raw_type: Key, raw_args: (action: Some(Press), keycode: Some(W)), action: Jump),
+                (raw_type: Key, raw_args: (action: Some(Release), keycode: Some(W)), action: Land),
+            ]
+        )
+    ]";
+
+    /// Jumping moved to E, and W ducks.
+    const JUMP_ON_E : &str = "[
+        Context(
+            id: Default,
+            mappings: [
+                (raw_type: Key, raw_args: (action: Some(Press), keycode: Some(E)), action: Jump),
+                (raw_type: Key, raw_args: (action: Some(Press), keycode: Some(W)), action: Duck),
+                (raw_type: Key, raw_args: (action: Some(Release), keycode: Some(W)), action: Land),
+            ]
+        )
+    ]";
+
+    fn key(keycode : KeyCode, action : RawInputAction) -> Vec<RawInput> {
+        vec![RawInput::new(0.0, DeviceType::Keyboard, 0,
+                           RawInputEvent::Key(keycode, action, RawInputModifiers::empty()))]
+    }
+
+    fn actions(mapped : Vec<(Option<usize>, remawin::Event<TestAction, TestContext>)>) -> Vec<TestAction> {
+        mapped.into_iter()
+            .filter_map(|(_, event)| match event {
+                remawin::Event::Controller(remawin::ControllerEvent::Action(action, _)) => Some(action),
+                _ => None
+            })
+            .collect()
+    }
+
+    fn mapper(bindings : &TempFile) -> TestMapper {
+        let mut mapper = TestMapper::new((800.0, 600.0));
+        mapper.load_bindings(bindings.path()).expect("Test bindings are valid");
+        mapper.push_context(TestContext::Default, 1);
+        mapper
+    }
+
+    /// Runs a frame without input, reloading if one was requested.
+    fn frame(mapper : &mut TestMapper) -> Vec<TestAction> {
+        actions(mapper.process_timed_players(&[], 1.0))
+    }
+
+    #[test]
+    fn text_mode_keeps_key_input_from_the_bindings() {
+        let bindings = TempFile::new("text_mode.ron", JUMP_ON_W);
+        let mut mapper = mapper(&bindings);
+        mapper.start_text_input(TextBuffer::new());
+        let mut typed = key(KeyCode::W, RawInputAction::Press);
+        typed.push(RawInput::new(0.0, DeviceType::Keyboard, 0, RawInputEvent::Char('w')));
+        assert!(mapper.process_raw_input_at(&typed, 1.0).is_empty());
+        assert!(mapper.process_raw_input_at(&key(KeyCode::Return, RawInputAction::Press), 1.0).is_empty());
+        assert_eq!(mapper.drain_text_events(), vec![TextEvent::Submitted("w".to_string())]);
+
+        // Escape leaves text mode, and keys pressed while typing stay out of the bindings until
+        // they are released
+        assert!(mapper.process_raw_input_at(&key(KeyCode::Escape, RawInputAction::Press), 1.0).is_empty());
+        assert_eq!(mapper.drain_text_events(), vec![TextEvent::Cancelled(TextBuffer::new())]);
+        assert!(!mapper.is_text_input());
+        assert!(mapper.process_raw_input_at(&key(KeyCode::W, RawInputAction::Release), 1.0).is_empty());
+        assert_eq!(actions(mapper.process_raw_input_at(&key(KeyCode::W, RawInputAction::Press), 1.0)),
+                   vec![TestAction::Jump]);
+    }
+
+    #[test]
+    fn context_stack_keeps_push_order() {
+        let mut mapper = TestMapper::new((800.0, 600.0));
+        mapper.push_context(TestContext::Default, 5);
+        mapper.push_context_on_top(TestContext::Menu);
+        mapper.push_context(TestContext::Pause, 2);
+        assert_eq!(mapper.active_contexts(),
+                   &[(TestContext::Default, 5), (TestContext::Menu, 6), (TestContext::Pause, 2)]);
+
+        // on top goes above the highest priority, not the latest push
+        mapper.push_context_on_top(TestContext::Default);
+        assert_eq!(mapper.active_contexts()[3], (TestContext::Default, 7));
+
+        // removing takes out the latest push of a context, wherever it is
+        assert!(mapper.remove_context(&TestContext::Default));
+        assert_eq!(mapper.active_contexts(),
+                   &[(TestContext::Default, 5), (TestContext::Menu, 6), (TestContext::Pause, 2)]);
+        assert!(mapper.remove_context(&TestContext::Menu));
+        assert!(!mapper.remove_context(&TestContext::Menu));
+        assert_eq!(mapper.pop_context(), Some(TestContext::Pause));
+        assert_eq!(mapper.active_contexts(), &[(TestContext::Default, 5)]);
+        assert_eq!(mapper.pop_context(), Some(TestContext::Default));
+        assert_eq!(mapper.pop_context(), None);
+        mapper.push_context_on_top(TestContext::Menu);
+        assert_eq!(mapper.active_contexts(), &[(TestContext::Menu, 1)]);
+    }
+
+    #[test]
+    fn captured_rebind_is_saved_once_the_frame_is_mapped() {
+        let bindings = TempFile::new("captured_rebind.ron", JUMP_ON_W);
+        let overrides = TempFile::missing("captured_rebind_user.ron");
+        let mut mapper = TestMapper::new((800.0, 600.0));
+        mapper.with_user_overrides(overrides.path());
+        mapper.load_bindings(bindings.path()).expect("Test bindings are valid");
+        mapper.push_context(TestContext::Default, 1);
+        mapper.start_rebind(TestContext::Default, TestAction::Jump);
+        assert!(mapper.process_raw_input_at(&key(KeyCode::E, RawInputAction::Press), 1.0).is_empty());
+        match mapper.take_rebind_result() {
+            Some(Ok(outcome)) => assert_eq!((outcome.action, outcome.swapped), (TestAction::Jump, None)),
+            other => panic!("expected a completed rebind, got {:?}", other)
+        };
+        assert!(overrides.as_path().exists());
+
+        // the captured key is held back until released, then jumps
+        assert!(mapper.process_raw_input_at(&key(KeyCode::E, RawInputAction::Release), 1.0).is_empty());
+        assert_eq!(actions(mapper.process_raw_input_at(&key(KeyCode::E, RawInputAction::Press), 1.0)),
+                   vec![TestAction::Jump]);
+        assert!(mapper.process_raw_input_at(&key(KeyCode::W, RawInputAction::Press), 1.0).is_empty());
+    }
+
+    #[test]
+    fn reload_keeps_contexts_and_held_keys() {
+        let bindings = TempFile::new("reload_keeps_contexts.ron", JUMP_ON_W);
+        let mut mapper = mapper(&bindings);
+        assert_eq!(actions(mapper.process_raw_input_at(&key(KeyCode::W, RawInputAction::Press), 1.0)),
+                   vec![TestAction::Jump]);
+
+        // W is still held, and its press is not reported again for the new bindings
+        bindings.write(JUMP_ON_E);
+        mapper.request_reload();
+        assert!(frame(&mut mapper).is_empty());
+        match mapper.take_reload_result() {
+            Some(Ok(())) => (),
+            other => panic!("expected a successful reload, got {:?}", other)
+        };
+        assert_eq!(mapper.active_contexts(), &[(TestContext::Default, 1)]);
+
+        assert_eq!(actions(mapper.process_raw_input_at(&key(KeyCode::W, RawInputAction::Release), 1.0)),
+                   vec![TestAction::Land]);
+        assert_eq!(actions(mapper.process_raw_input_at(&key(KeyCode::E, RawInputAction::Press), 1.0)),
+                   vec![TestAction::Jump]);
+        assert_eq!(actions(mapper.process_raw_input_at(&key(KeyCode::W, RawInputAction::Press), 1.0)),
+                   vec![TestAction::Duck]);
+    }
+
+    #[test]
+    fn failed_reload_keeps_the_old_bindings() {
+        let bindings = TempFile::new("failed_reload.ron", JUMP_ON_W);
+        let mut mapper = mapper(&bindings);
+        bindings.write("[ Context( id: Default, mappings: [ (raw_type: Key");
+        mapper.request_reload();
+        frame(&mut mapper);
+        match mapper.take_reload_result() {
+            Some(Err(_)) => (),
+            other => panic!("expected the reload to fail, got {:?}", other)
+        };
+        assert!(mapper.take_reload_result().is_none());
+        assert_eq!(mapper.active_contexts(), &[(TestContext::Default, 1)]);
+        assert_eq!(actions(mapper.process_raw_input_at(&key(KeyCode::W, RawInputAction::Press), 1.0)),
+                   vec![TestAction::Jump]);
+    }
+
+    #[test]
+    fn reload_only_runs_when_requested_or_watched() {
+        let bindings = TempFile::new("reload_on_request.ron", JUMP_ON_W);
+        let mut mapper = mapper(&bindings);
+        bindings.write(JUMP_ON_E);
+        frame(&mut mapper);
+        assert!(mapper.take_reload_result().is_none());
+        assert_eq!(actions(mapper.process_raw_input_at(&key(KeyCode::W, RawInputAction::Press), 1.0)),
+                   vec![TestAction::Jump]);
+    }
+}
+
@@ -0,0 +1,336 @@
+use remawin::raw::{RawInput, RawInputEvent, RawInputAction};
+use remawin::types::{DeviceType, KeyCode};
+use amethyst::ecs::resources::Camera;
+use cgmath::Point2;
+
+use cursor::{cursor_to_world, is_valid_size};
+use touch::{TOUCH_BUTTON, TOUCH_REGION_DEVICE_BASE};
+
+/// Snapshot of the keyboard, mouse and window, updated by the mapper from every frame of raw
+/// input. Copy it into the world with `clone_from` each frame to expose it to systems.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputState {
+    keys : Vec<KeyCode>,
+    keys_pressed : Vec<KeyCode>,
+    keys_released : Vec<KeyCode>,
+    buttons : Vec<u32>,
+    buttons_pressed : Vec<u32>,
+    buttons_released : Vec<u32>,
+    cursor : Option<(f64, f64)>,
+    motion : (f64, f64),
+    touches : Vec<(u32, (f64, f64))>,
+    touch_regions : Vec<(u32, u32, (f64, f64))>,
+    focused : bool,
+    size : (f64, f64),
+    frame_start : f64,
+    frame_time : f64
+}
+
+impl InputState {
+    pub fn new(size : (f64, f64)) -> InputState {
+        InputState {
+            keys : Vec::default(),
+            keys_pressed : Vec::default(),
+            keys_released : Vec::default(),
+            buttons : Vec::default(),
+            buttons_pressed : Vec::default(),
+            buttons_released : Vec::default(),
+            cursor : None,
+            motion : (0.0, 0.0),
+            touches : Vec::default(),
+            touch_regions : Vec::default(),
+            focused : true,
+            size : size,
+            frame_start : 0.0,
+            frame_time : 0.0
+        }
+    }
+
+    pub fn is_key_down(&self, keycode : &KeyCode) -> bool {
+        self.keys.contains(keycode)
+    }
+
+    /// True if the key went down during the last frame.
+    pub fn key_pressed(&self, keycode : &KeyCode) -> bool {
+        self.keys_pressed.contains(keycode)
+    }
+
+    /// True if the key went up during the last frame.
+    pub fn key_released(&self, keycode : &KeyCode) -> bool {
+        self.keys_released.contains(keycode)
+    }
+
+    pub fn keys_down(&self) -> &[KeyCode] {
+        &self.keys
+    }
+
+    /// Whether a mouse button is held, by the button numbers used in `RawInputEvent::Button`.
+    pub fn is_button_down(&self, button : u32) -> bool {
+        self.buttons.contains(&button)
+    }
+
+    pub fn button_pressed(&self, button : u32) -> bool {
+        self.buttons_pressed.contains(&button)
+    }
+
+    pub fn button_released(&self, button : u32) -> bool {
+        self.buttons_released.contains(&button)
+    }
+
+    pub fn buttons_down(&self) -> &[u32] {
+        &self.buttons
+    }
+
+    /// Cursor position in [0, 1], from the top left corner of the window.
+    pub fn cursor(&self) -> Option<(f64, f64)> {
+        self.cursor
+    }
+
+    /// Cursor position in pixels.
+    pub fn cursor_pixels(&self) -> Option<(f64, f64)> {
+        self.cursor.map(|(x, y)| (x * self.size.0, y * self.size.1))
+    }
+
+    /// Cursor position in world space, see `cursor_to_world`.
+    pub fn cursor_world(&self, camera : &Camera) -> Option<Point2<f32>> {
+        self.cursor.and_then(|cursor| cursor_to_world(cursor, camera))
+    }
+
+    /// Cursor movement during the last frame, in the same units as `cursor`.
+    pub fn motion(&self) -> (f64, f64) {
+        self.motion
+    }
+
+    /// Fingers on the screen by id, with their position in the same units as `cursor`.
+    pub fn touches(&self) -> &[(u32, (f64, f64))] {
+        &self.touches
+    }
+
+    /// Position of the finger holding down the touch region of `button`, in the same units as
+    /// `cursor`, or `None` while the region is not touched.
+    pub fn touch_region(&self, button : u32) -> Option<(f64, f64)> {
+        self.touch_regions.iter()
+            .find(|&&(_, b, _)| b == button)
+            .map(|&(_, _, position)| position)
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Window size in pixels.
+    pub fn size(&self) -> (f64, f64) {
+        self.size
+    }
+
+    /// Time of the last processed frame, in seconds.
+    pub fn frame_time(&self) -> f64 {
+        self.frame_time
+    }
+
+    /// Time between the previous frame and the last one, 0 before two frames were processed.
+    pub fn frame_duration(&self) -> f64 {
+        if self.frame_start > 0.0 {
+            self.frame_time - self.frame_start
+        } else {
+            0.0
+        }
+    }
+
+    /// Where a timestamp from the last frame falls within it, from 0 at the end of the previous
+    /// frame to 1 at the time of the last one. Input mapped without a receive time is at 1.
+    pub fn frame_offset(&self, t : f64) -> f64 {
+        let duration = self.frame_duration();
+        if duration > 0.0 {
+            ((t - self.frame_start) / duration).max(0.0).min(1.0)
+        } else {
+            1.0
+        }
+    }
+
+    /// How long before the last frame time an input with timestamp `t` happened.
+    pub fn latency(&self, t : f64) -> f64 {
+        self.frame_time - t
+    }
+
+    pub fn set_size(&mut self, size : (f64, f64)) {
+        self.size = size;
+    }
+
+    /// Starts a new frame at time `now`, forgetting what was pressed and released in the
+    /// previous one, and applies `raw_input` to it.
+    pub fn update(&mut self, raw_input : &[RawInput], now : f64) {
+        self.frame_start = self.frame_time;
+        self.frame_time = now;
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        self.buttons_pressed.clear();
+        self.buttons_released.clear();
+        self.motion = (0.0, 0.0);
+        for raw in raw_input {
+            match raw.event {
+                RawInputEvent::Key(ref keycode, ref action, _) => {
+                    update_held(&mut self.keys,
+                                &mut self.keys_pressed,
+                                &mut self.keys_released,
+                                keycode,
+                                action)
+                },
+                RawInputEvent::Button(button, position, ref action, _) => {
+                    if raw.device_type == DeviceType::Mouse {
+                        update_held(&mut self.buttons,
+                                    &mut self.buttons_pressed,
+                                    &mut self.buttons_released,
+                                    &button,
+                                    action)
+                    } else if raw.device_type == DeviceType::Touch && raw.device_id >= TOUCH_REGION_DEVICE_BASE {
+                        let region = raw.device_id;
+                        self.touch_regions.retain(|&(id, _, _)| id != region);
+                        if let RawInputAction::Press = *action {
+                            self.touch_regions.push((region, button, position));
+                        }
+                    } else if raw.device_type == DeviceType::Touch && button == TOUCH_BUTTON {
+                        let finger = raw.device_id;
+                        self.touches.retain(|&(id, _)| id != finger);
+                        if let RawInputAction::Press = *action {
+                            self.touches.push((finger, position));
+                        }
+                    }
+                },
+                RawInputEvent::CursorPosition(x, y) => {
+                    if raw.device_type == DeviceType::Touch {
+                        if let Some(touch) = self.touches.iter_mut().find(|t| t.0 == raw.device_id) {
+                            touch.1 = (x, y);
+                        }
+                        if let Some(region) = self.touch_regions.iter_mut().find(|r| r.0 == raw.device_id) {
+                            region.2 = (x, y);
+                        }
+                    } else {
+                        self.cursor = Some((x, y));
+                    }
+                },
+                RawInputEvent::Motion(dx, dy) => {
+                    if raw.device_type == DeviceType::Mouse {
+                        self.motion = (self.motion.0 + dx, self.motion.1 + dy);
+                    }
+                },
+                RawInputEvent::Resize(x, y) => {
+                    if is_valid_size((x as f64, y as f64)) {
+                        self.size = (x as f64, y as f64);
+                    }
+                },
+                RawInputEvent::Focus(focused) => {
+                    self.focused = focused;
+                    // releases that happen while unfocused are never delivered
+                    if !focused {
+                        self.keys_released.extend(self.keys.drain(..));
+                        self.buttons_released.extend(self.buttons.drain(..));
+                    }
+                },
+                _ => ()
+            };
+        }
+    }
+}
+
+fn update_held<T>(held : &mut Vec<T>,
+                  pressed : &mut Vec<T>,
+                  released : &mut Vec<T>,
+                  input : &T,
+                  action : &RawInputAction)
+    where T: PartialEq + Clone {
+    match *action {
+        RawInputAction::Press => {
+            // key repeat sends more presses while held
+            if !held.contains(input) {
+                held.push(input.clone());
+                pressed.push(input.clone());
+            }
+        },
+        _ => {
+            if held.contains(input) {
+                held.retain(|h| h != input);
+                released.push(input.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use remawin::raw::RawInputModifiers;
+
+    fn key(keycode : KeyCode, action : RawInputAction) -> RawInput {
+        RawInput::new(0.0, DeviceType::Keyboard, 0, RawInputEvent::Key(keycode, action, RawInputModifiers::empty()))
+    }
+
+    fn button(button : u32, action : RawInputAction) -> RawInput {
+        let event = RawInputEvent::Button(button, (0.5, 0.5), action, RawInputModifiers::empty());
+        RawInput::new(0.0, DeviceType::Mouse, 0, event)
+    }
+
+    fn window(event : RawInputEvent) -> RawInput {
+        RawInput::new(0.0, DeviceType::Window, 0, event)
+    }
+
+    #[test]
+    fn presses_and_releases_last_one_frame_and_holds_persist() {
+        let mut state = InputState::new((800.0, 600.0));
+        state.update(&[key(KeyCode::W, RawInputAction::Press),
+                       key(KeyCode::A, RawInputAction::Press),
+                       button(1, RawInputAction::Press)], 1.0);
+        assert!(state.key_pressed(&KeyCode::W) && state.is_key_down(&KeyCode::W));
+        assert!(state.button_pressed(1) && state.is_button_down(1));
+
+        // a repeated press is not a new one
+        state.update(&[key(KeyCode::W, RawInputAction::Press),
+                       key(KeyCode::A, RawInputAction::Release)], 1.25);
+        assert!(!state.key_pressed(&KeyCode::W) && state.is_key_down(&KeyCode::W));
+        assert!(state.key_released(&KeyCode::A) && !state.is_key_down(&KeyCode::A));
+        assert!(!state.button_pressed(1) && state.is_button_down(1));
+        assert_eq!(state.keys_down(), &[KeyCode::W]);
+
+        state.update(&[], 1.5);
+        assert!(!state.key_released(&KeyCode::A));
+        assert_eq!((state.keys_down(), state.buttons_down()), (&[KeyCode::W][..], &[1][..]));
+        assert_eq!(state.frame_duration(), 0.25);
+        assert_eq!(state.frame_offset(1.375), 0.5);
+    }
+
+    #[test]
+    fn motion_adds_up_within_a_frame_only() {
+        let mut state = InputState::new((800.0, 600.0));
+        let motion = |dx, dy| RawInput::new(0.0, DeviceType::Mouse, 0, RawInputEvent::Motion(dx, dy));
+        state.update(&[motion(0.25, 0.0), motion(0.25, -0.5),
+                       RawInput::new(0.0, DeviceType::Mouse, 0, RawInputEvent::CursorPosition(0.5, 0.25))], 1.0);
+        assert_eq!(state.motion(), (0.5, -0.5));
+        assert_eq!(state.cursor_pixels(), Some((400.0, 150.0)));
+        state.update(&[], 1.5);
+        assert_eq!(state.motion(), (0.0, 0.0));
+        assert_eq!(state.cursor(), Some((0.5, 0.25)));
+    }
+
+    #[test]
+    fn losing_focus_releases_everything_held() {
+        let mut state = InputState::new((800.0, 600.0));
+        state.update(&[key(KeyCode::W, RawInputAction::Press), button(1, RawInputAction::Press)], 1.0);
+        state.update(&[window(RawInputEvent::Focus(false))], 1.5);
+        assert!(!state.is_focused());
+        assert!(state.key_released(&KeyCode::W) && state.button_released(1));
+        assert!(state.keys_down().is_empty() && state.buttons_down().is_empty());
+
+        state.update(&[window(RawInputEvent::Focus(true))], 2.0);
+        assert!(state.is_focused());
+        assert!(!state.key_released(&KeyCode::W));
+    }
+
+    #[test]
+    fn size_ignores_minimised_windows() {
+        let mut state = InputState::new((800.0, 600.0));
+        state.update(&[window(RawInputEvent::Resize(1024, 768))], 1.0);
+        assert_eq!(state.size(), (1024.0, 768.0));
+        state.update(&[window(RawInputEvent::Resize(0, 0))], 1.5);
+        assert_eq!(state.size(), (1024.0, 768.0));
+    }
+}
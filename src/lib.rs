@@ -0,0 +1,40 @@
+extern crate amethyst;
+extern crate remawin;
+extern crate glutin;
+extern crate time;
+extern crate shrev;
+extern crate cgmath;
+extern crate collision;
+extern crate serde;
+extern crate ron;
+#[cfg(feature = "gilrs")]
+extern crate gilrs;
+
+#[macro_use]
+extern crate serde_derive;
+
+pub mod input_mapper;
+pub mod gamepad;
+pub mod touch;
+pub mod player;
+pub mod remapper;
+pub mod recording;
+pub mod bindings;
+pub mod state;
+pub mod combo;
+pub mod axis;
+pub mod text;
+pub mod input_state;
+pub mod cursor;
+pub mod metrics;
+pub mod event;
+pub mod coll;
+
+#[cfg(test)]
+mod test_util;
+
+pub use input_mapper::AmethystEventMapper;
+pub use state::ContextState;
+pub use input_state::InputState;
+pub use text::TextInput;
+pub use metrics::InputMetrics;
@@ -0,0 +1,225 @@
+use remawin::raw::{RawInput, RawInputEvent};
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Upper bounds of the histogram buckets, in seconds. A last bucket catches everything above.
+const BUCKET_BOUNDS : [f64; 12] = [0.00001, 0.00002, 0.00005,
+                                   0.0001, 0.0002, 0.0005,
+                                   0.001, 0.002, 0.005,
+                                   0.01, 0.02, 0.05];
+
+/// Durations bucketed on a roughly logarithmic scale from 10µs to 50ms.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    buckets : Vec<u64>,
+    count : u64,
+    sum : f64,
+    max : f64
+}
+
+impl Histogram {
+    pub fn new() -> Histogram {
+        Histogram {
+            buckets : vec![0; BUCKET_BOUNDS.len() + 1],
+            count : 0,
+            sum : 0.0,
+            max : 0.0
+        }
+    }
+
+    pub fn record(&mut self, seconds : f64) {
+        let seconds = seconds.max(0.0);
+        let bucket = BUCKET_BOUNDS.iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(BUCKET_BOUNDS.len());
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.sum += seconds;
+        if seconds > self.max {
+            self.max = seconds;
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.count > 0 {
+            self.sum / self.count as f64
+        } else {
+            0.0
+        }
+    }
+
+    pub fn max(&self) -> f64 {
+        self.max
+    }
+
+    /// Upper bound of the bucket holding the `p` quantile, `p` in [0, 1]. Values above the
+    /// last bucket report the largest value seen.
+    pub fn percentile(&self, p : f64) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let target = (p.max(0.0).min(1.0) * self.count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            seen += *count;
+            if seen >= target {
+                return BUCKET_BOUNDS.get(index).cloned().unwrap_or(self.max).min(self.max);
+            }
+        }
+        self.max
+    }
+
+    /// Counts per bucket, paired with the bucket's upper bound in seconds.
+    pub fn buckets(&self) -> Vec<(f64, u64)> {
+        self.buckets.iter()
+            .enumerate()
+            .map(|(index, count)| (BUCKET_BOUNDS.get(index).cloned().unwrap_or(::std::f64::INFINITY), *count))
+            .collect()
+    }
+}
+
+impl fmt::Display for Histogram {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "n={} mean={:.1}µs p50<={:.1}µs p99<={:.1}µs max={:.1}µs",
+               self.count,
+               self.mean() * 1.0e6,
+               self.percentile(0.5) * 1.0e6,
+               self.percentile(0.99) * 1.0e6,
+               self.max * 1.0e6)
+    }
+}
+
+/// Counters and timings of the input pipeline, collected by the mapper once enabled with
+/// `AmethystEventMapper::enable_metrics`. Copy it into the world each frame to expose it to
+/// systems, like `InputState`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputMetrics {
+    /// Frames processed.
+    pub frames : u64,
+    /// Raw input processed, by event type.
+    pub events : BTreeMap<&'static str, u64>,
+    /// Most raw input seen in a single frame.
+    pub max_events_per_frame : usize,
+    /// Time spent turning window events into raw input.
+    pub mapping : Histogram,
+    /// Time spent in the remappers, shared and per player.
+    pub remapping : Histogram,
+    /// Time spent processing a frame of raw input, remapping included.
+    pub processing : Histogram,
+    /// Time from each raw input's capture until the frame holding it was mapped to events. Only
+    /// input with a known capture time counts: window events given a time by the caller, and
+    /// events from live gamepad sources.
+    pub latency : Histogram
+}
+
+impl InputMetrics {
+    pub fn new() -> InputMetrics {
+        InputMetrics {
+            frames : 0,
+            events : BTreeMap::new(),
+            max_events_per_frame : 0,
+            mapping : Histogram::new(),
+            remapping : Histogram::new(),
+            processing : Histogram::new(),
+            latency : Histogram::new()
+        }
+    }
+
+    pub fn total_events(&self) -> u64 {
+        self.events.values().sum()
+    }
+
+    /// Counts one frame of raw input.
+    pub fn count_frame(&mut self, raw_input : &[RawInput]) {
+        self.frames += 1;
+        if raw_input.len() > self.max_events_per_frame {
+            self.max_events_per_frame = raw_input.len();
+        }
+        for raw in raw_input {
+            *self.events.entry(event_kind(&raw.event)).or_insert(0) += 1;
+        }
+    }
+}
+
+impl fmt::Display for InputMetrics {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "frames: {}, events: {}, most in a frame: {}",
+                 self.frames, self.total_events(), self.max_events_per_frame)?;
+        for (kind, count) in &self.events {
+            writeln!(f, "  {}: {}", kind, count)?;
+        }
+        writeln!(f, "mapping: {}", self.mapping)?;
+        writeln!(f, "remapping: {}", self.remapping)?;
+        writeln!(f, "processing: {}", self.processing)?;
+        write!(f, "latency: {}", self.latency)
+    }
+}
+
+fn event_kind(event : &RawInputEvent) -> &'static str {
+    match *event {
+        RawInputEvent::Key(..) => "Key",
+        RawInputEvent::Button(..) => "Button",
+        RawInputEvent::Motion(..) => "Motion",
+        RawInputEvent::CursorPosition(..) => "CursorPosition",
+        RawInputEvent::Scroll(..) => "Scroll",
+        RawInputEvent::Axis(..) => "Axis",
+        RawInputEvent::Char(..) => "Char",
+        RawInputEvent::Resize(..) => "Resize",
+        RawInputEvent::Focus(..) => "Focus",
+        RawInputEvent::Close => "Close",
+        RawInputEvent::Connected => "Connected",
+        RawInputEvent::Disconnected => "Disconnected"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ten samples: three in the first bucket, one in the second, three in the one up to
+    /// 500µs, two in the one up to 5ms, and one above every bound.
+    fn samples() -> Histogram {
+        let mut histogram = Histogram::new();
+        for &seconds in &[0.000005, 0.000005, 0.00001, 0.000015, 0.0003, 0.0003, 0.0003, 0.004, 0.004, 0.08] {
+            histogram.record(seconds);
+        }
+        histogram
+    }
+
+    #[test]
+    fn samples_land_in_the_bucket_of_their_upper_bound() {
+        let histogram = samples();
+        let counts : Vec<u64> = histogram.buckets().iter().map(|&(_, count)| count).collect();
+        assert_eq!(counts, vec![3, 1, 0, 0, 0, 3, 0, 0, 2, 0, 0, 0, 1]);
+        assert_eq!(histogram.buckets().last(), Some(&(::std::f64::INFINITY, 1)));
+        assert_eq!((histogram.count(), histogram.max()), (10, 0.08));
+    }
+
+    #[test]
+    fn percentiles_report_the_bound_of_their_bucket() {
+        let histogram = samples();
+        assert_eq!(histogram.percentile(0.0), 0.00001);
+        assert_eq!(histogram.percentile(0.25), 0.00001);
+        assert_eq!(histogram.percentile(0.5), 0.0005);
+        assert_eq!(histogram.percentile(0.85), 0.005);
+        // above the last bound, the largest sample is all that is known
+        assert_eq!(histogram.percentile(0.95), 0.08);
+        assert_eq!(histogram.percentile(1.0), 0.08);
+    }
+
+    #[test]
+    fn percentiles_never_exceed_the_largest_sample() {
+        let mut histogram = Histogram::new();
+        assert_eq!(histogram.percentile(0.5), 0.0);
+        histogram.record(0.000015);
+        histogram.record(-1.0);
+        assert_eq!(histogram.percentile(0.5), 0.00001);
+        assert_eq!(histogram.percentile(1.0), 0.000015);
+        assert_eq!(histogram.mean(), 0.0000075);
+    }
+}
@@ -0,0 +1,62 @@
+use remawin::raw::RawInput;
+use remawin::types::DeviceType;
+
+use remapper::BindingsReMapper;
+
+use std::hash::Hash;
+use std::cmp::Eq;
+use std::fmt::Debug;
+use std::clone::Clone;
+
+/// A local player slot. Each player has its own remapper, so bindings can be written once per
+/// generic action and the player is known from which remapper produced the event.
+pub struct Player<ACTION, ID>
+    where ACTION: Hash + Eq + Clone,
+          ID: Hash + Eq + Clone + Debug {
+    remapper : BindingsReMapper<ACTION, ID>,
+    devices : Vec<(DeviceType, u32)>
+}
+
+impl <ACTION, ID> Player<ACTION, ID>
+    where ACTION: Hash + Eq + Clone,
+          ID: Hash + Eq + Clone + Debug {
+
+    pub fn new() -> Player<ACTION, ID> {
+        Player {
+            remapper : BindingsReMapper::new(),
+            devices : Vec::default()
+        }
+    }
+
+    pub fn remapper(&self) -> &BindingsReMapper<ACTION, ID> {
+        &self.remapper
+    }
+
+    pub fn remapper_mut(&mut self) -> &mut BindingsReMapper<ACTION, ID> {
+        &mut self.remapper
+    }
+
+    pub fn devices(&self) -> &[(DeviceType, u32)] {
+        &self.devices
+    }
+
+    /// Assigns a device to this player. A device may be assigned to several players, which is
+    /// how players share a keyboard using different contexts.
+    pub fn assign(&mut self, device_type : DeviceType, device_id : u32) {
+        if !self.owns_device(&device_type, device_id) {
+            self.devices.push((device_type, device_id));
+        }
+    }
+
+    pub fn release(&mut self, device_type : &DeviceType, device_id : u32) {
+        self.devices.retain(|&(ref t, id)| !(t == device_type && id == device_id));
+    }
+
+    pub fn owns_device(&self, device_type : &DeviceType, device_id : u32) -> bool {
+        self.devices.iter().any(|&(ref t, id)| t == device_type && id == device_id)
+    }
+
+    pub fn owns(&self, raw : &RawInput) -> bool {
+        self.owns_device(&raw.device_type, raw.device_id)
+    }
+}
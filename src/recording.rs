@@ -0,0 +1,133 @@
+use remawin::raw::RawInput;
+use ron;
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::io;
+use std::path::Path;
+
+/// Raw input produced by the mapper during one call to `process`. Each `RawInput` keeps the
+/// timestamp it was stamped with when it was mapped, and `time` is the time of the frame.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    #[serde(default)]
+    pub time : f64,
+    pub raw_input : Vec<RawInput>
+}
+
+impl RecordedFrame {
+    pub fn new(time : f64, raw_input : Vec<RawInput>) -> RecordedFrame {
+        RecordedFrame {
+            time : time,
+            raw_input : raw_input
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InputRecording {
+    pub frames : Vec<RecordedFrame>
+}
+
+#[derive(Debug)]
+pub enum RecordingError {
+    Io(io::Error),
+    Serialize(String),
+    Deserialize(String)
+}
+
+impl From<io::Error> for RecordingError {
+    fn from(err : io::Error) -> RecordingError {
+        RecordingError::Io(err)
+    }
+}
+
+impl InputRecording {
+    pub fn new() -> InputRecording {
+        InputRecording {
+            frames : Vec::default()
+        }
+    }
+
+    pub fn push_frame(&mut self, time : f64, raw_input : Vec<RawInput>) {
+        self.frames.push(RecordedFrame::new(time, raw_input));
+    }
+
+    pub fn save<P : AsRef<Path>>(&self, path : P) -> Result<(), RecordingError> {
+        let data = ron::ser::to_string(self)
+            .map_err(|err| RecordingError::Serialize(format!("{:?}", err)))?;
+        let mut file = File::create(path)?;
+        file.write_all(data.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn load<P : AsRef<Path>>(path : P) -> Result<InputRecording, RecordingError> {
+        let mut data = String::new();
+        File::open(path)?.read_to_string(&mut data)?;
+        ron::de::from_str(&data).map_err(|err| RecordingError::Deserialize(format!("{:?}", err)))
+    }
+}
+
+/// Plays back a recording in place of live window events, either one frame at a time or by
+/// the recorded frame times.
+#[derive(Debug, Clone)]
+pub struct InputReplay {
+    recording : InputRecording,
+    next_frame : usize,
+    offset : Option<f64>
+}
+
+impl InputReplay {
+    pub fn new(recording : InputRecording) -> InputReplay {
+        InputReplay {
+            recording : recording,
+            next_frame : 0,
+            offset : None
+        }
+    }
+
+    pub fn next_frame(&mut self) -> Option<RecordedFrame> {
+        let frame = self.recording.frames.get(self.next_frame).cloned();
+        if frame.is_some() {
+            self.next_frame += 1;
+        }
+        frame
+    }
+
+    /// Frames that are due at `now`, oldest first. The first call lines the start of the
+    /// recording up with `now`; frames and their input are shifted onto that clock, so they
+    /// keep their recorded spacing whatever rate this is called at.
+    pub fn frames_until(&mut self, now : f64) -> Vec<RecordedFrame> {
+        let offset = match (self.offset, self.recording.frames.get(self.next_frame)) {
+            (Some(offset), _) => offset,
+            (None, Some(frame)) => now - frame.time,
+            (None, None) => return Vec::default()
+        };
+        self.offset = Some(offset);
+        let mut due = Vec::default();
+        while let Some(frame) = self.recording.frames.get(self.next_frame) {
+            if frame.time + offset > now {
+                break;
+            }
+            let raw_input = frame.raw_input.iter()
+                .map(|raw| {
+                    let mut raw = raw.clone();
+                    raw.time += offset;
+                    raw
+                })
+                .collect();
+            due.push(RecordedFrame::new(frame.time + offset, raw_input));
+            self.next_frame += 1;
+        }
+        due
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_frame >= self.recording.frames.len()
+    }
+
+    pub fn rewind(&mut self) {
+        self.next_frame = 0;
+        self.offset = None;
+    }
+}
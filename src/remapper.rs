@@ -0,0 +1,334 @@
+use remawin;
+use remawin::raw::{RawInput, RawInputEvent, RawInputAction, RawInputModifiers};
+use remawin::types::{DeviceType, KeyCode};
+use remawin::{Context, InputReMapper};
+
+use bindings::{BindingsError, ContextBindings, Mapping, ModifierFilter, to_contexts};
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use std::hash::Hash;
+use std::cmp::Eq;
+use std::fmt::Debug;
+use std::clone::Clone;
+use std::path::{Path, PathBuf};
+
+/// A key or button on a specific device, to follow it from press to release.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeldInput {
+    Key(DeviceType, u32, KeyCode),
+    Button(DeviceType, u32, u32)
+}
+
+impl HeldInput {
+    /// The key or button of a key or button event, with its action and modifiers.
+    pub fn from_raw(raw : &RawInput) -> Option<(HeldInput, RawInputAction, RawInputModifiers)> {
+        match raw.event {
+            RawInputEvent::Key(ref keycode, ref action, modifiers) =>
+                Some((HeldInput::Key(raw.device_type.clone(), raw.device_id, keycode.clone()),
+                      action.clone(),
+                      modifiers)),
+            RawInputEvent::Button(button, _, ref action, modifiers) =>
+                Some((HeldInput::Button(raw.device_type.clone(), raw.device_id, button),
+                      action.clone(),
+                      modifiers)),
+            _ => None
+        }
+    }
+}
+
+/// Remapper for one set of bindings, checking the modifiers that mappings require or exclude.
+/// The remapper itself ignores modifiers, so mappings with a modifier filter go to a separate
+/// remapper per filter, which only sees key and button presses passing it, and the releases
+/// of the presses it saw. A mapping without modifiers excludes the modifiers that other
+/// mappings of the same input in its context require, so S does not fire along with Ctrl+S.
+pub struct BindingsReMapper<ACTION, ID>
+    where ACTION: Hash + Eq + Clone,
+          ID: Hash + Eq + Clone + Debug {
+    remapper : InputReMapper<ACTION, ID>,
+    layers : Vec<ModifierLayer<ACTION, ID>>,
+    path : Option<PathBuf>,
+    active : Vec<(ID, u32)>,
+    held : Vec<(HeldInput, RawInput)>
+}
+
+struct ModifierLayer<ACTION, ID>
+    where ACTION: Hash + Eq + Clone,
+          ID: Hash + Eq + Clone + Debug {
+    filter : ModifierFilter,
+    remapper : InputReMapper<ACTION, ID>,
+    held : Vec<HeldInput>
+}
+
+impl <ACTION, ID> ModifierLayer<ACTION, ID>
+    where ACTION: Hash + Eq + Clone,
+          ID: Hash + Eq + Clone + Debug {
+
+    fn passes(&mut self, raw : &RawInput) -> bool {
+        match HeldInput::from_raw(raw) {
+            Some((input, RawInputAction::Press, modifiers)) => {
+                if self.held.contains(&input) {
+                    true
+                } else if self.filter.accepts(modifiers) {
+                    self.held.push(input);
+                    true
+                } else {
+                    false
+                }
+            },
+            Some((input, RawInputAction::Release, _)) => {
+                let held = self.held.contains(&input);
+                self.held.retain(|h| h != &input);
+                held
+            },
+            Some((input, _, _)) => self.held.contains(&input),
+            None => match raw.event {
+                RawInputEvent::Focus(focused) => {
+                    // releases that happen while unfocused are never delivered
+                    if !focused {
+                        self.held.clear();
+                    }
+                    true
+                },
+                _ => false
+            }
+        }
+    }
+
+    fn process_raw_input(&mut self, raw_input : &[RawInput]) -> Vec<remawin::Event<ACTION, ID>> {
+        let passed : Vec<RawInput> = raw_input.iter()
+            .filter(|raw| self.passes(raw))
+            .cloned()
+            .collect();
+        if passed.len() > 0 {
+            self.remapper.process_raw_input(&passed)
+        } else {
+            Vec::default()
+        }
+    }
+}
+
+impl <ACTION, ID> BindingsReMapper<ACTION, ID>
+    where ACTION: Hash + Eq + Clone,
+          ID: Hash + Eq + Clone + Debug {
+
+    pub fn new() -> BindingsReMapper<ACTION, ID> {
+        BindingsReMapper {
+            remapper : InputReMapper::new(),
+            layers : Vec::default(),
+            path : None,
+            active : Vec::default(),
+            held : Vec::default()
+        }
+    }
+
+    /// Adds a context built in code, which has no modifier filters. Contexts added this way
+    /// are dropped when the bindings are replaced.
+    pub fn with_context(&mut self, context : Context<ACTION, ID>) -> &mut Self {
+        self.remapper.with_context(context);
+        self
+    }
+
+    /// Bindings file the contexts were loaded from, if any.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_ref().map(|p| p.as_path())
+    }
+
+    pub fn activate_context(&mut self, context : &ID, priority : u32) {
+        self.active.retain(|&(ref id, _)| id != context);
+        self.active.push((context.clone(), priority));
+        self.remapper.activate_context(context, priority);
+        for layer in self.layers.iter_mut() {
+            layer.remapper.activate_context(context, priority);
+        }
+    }
+
+    pub fn deactivate_context(&mut self, context : &ID) {
+        self.active.retain(|&(ref id, _)| id != context);
+        self.remapper.deactivate_context(context);
+        for layer in self.layers.iter_mut() {
+            layer.remapper.deactivate_context(context);
+        }
+    }
+
+    /// Maps one frame of raw input. Events of mappings without a modifier filter come first.
+    pub fn process_raw_input(&mut self, raw_input : &Vec<RawInput>) -> Vec<remawin::Event<ACTION, ID>> {
+        for raw in raw_input {
+            match HeldInput::from_raw(raw) {
+                Some((input, RawInputAction::Press, _)) => {
+                    if !self.held.iter().any(|&(ref h, _)| h == &input) {
+                        self.held.push((input, raw.clone()));
+                    }
+                },
+                Some((input, RawInputAction::Release, _)) =>
+                    self.held.retain(|&(ref h, _)| h != &input),
+                _ => if let RawInputEvent::Focus(false) = raw.event {
+                    self.held.clear();
+                }
+            };
+        }
+        let mut mapped = self.remapper.process_raw_input(raw_input);
+        for layer in self.layers.iter_mut() {
+            mapped.extend(layer.process_raw_input(raw_input));
+        }
+        mapped
+    }
+
+    /// Takes over the contexts of `other`, keeping the active contexts and the state of held
+    /// keys and buttons: presses still held are replayed to the new contexts, without
+    /// reporting the actions they map to again.
+    pub fn replace_with(&mut self, other : BindingsReMapper<ACTION, ID>) {
+        self.remapper = other.remapper;
+        self.layers = other.layers;
+        self.path = other.path;
+        let active = self.active.clone();
+        for &(ref context, priority) in &active {
+            self.activate_context(context, priority);
+        }
+        let held : Vec<RawInput> = self.held.iter().map(|&(_, ref raw)| raw.clone()).collect();
+        if held.len() > 0 {
+            self.remapper.process_raw_input(&held);
+            for layer in self.layers.iter_mut() {
+                layer.process_raw_input(&held);
+            }
+        }
+    }
+}
+
+impl <ACTION, ID> BindingsReMapper<ACTION, ID>
+    where ACTION: Hash + Eq + Clone + Serialize + DeserializeOwned,
+          ID: Hash + Eq + Clone + Debug + Serialize + DeserializeOwned {
+
+    /// Builds a remapper holding the validated bindings read from `path`, with no active
+    /// contexts.
+    pub fn from_bindings(path : &Path,
+                         contexts : &[ContextBindings<ACTION, ID>])
+        -> Result<BindingsReMapper<ACTION, ID>, BindingsError> {
+        let mapping_filters : Vec<Vec<ModifierFilter>> = contexts.iter()
+            .map(|context| context.mappings.iter()
+                 .map(|mapping| effective_filter(mapping, &context.mappings))
+                 .collect())
+            .collect();
+        let mut filters : Vec<ModifierFilter> = vec![ModifierFilter::none()];
+        for filter in mapping_filters.iter().flat_map(|f| f.iter()) {
+            if !filters.contains(filter) {
+                filters.push(*filter);
+            }
+        }
+        let mut built = BindingsReMapper::new();
+        built.path = Some(path.to_path_buf());
+        for filter in filters {
+            let split : Vec<ContextBindings<ACTION, ID>> = contexts.iter()
+                .zip(mapping_filters.iter())
+                .map(|(context, mapping_filters)| ContextBindings {
+                    id : context.id.clone(),
+                    mappings : context.mappings.iter()
+                        .zip(mapping_filters.iter())
+                        .filter(|&(_, f)| *f == filter)
+                        .map(|(m, _)| m.clone())
+                        .collect()
+                })
+                .collect();
+            let mut remapper = InputReMapper::new();
+            for context in to_contexts(path, &split)? {
+                remapper.with_context(context);
+            }
+            if filter.is_empty() {
+                built.remapper = remapper;
+            } else {
+                built.layers.push(ModifierLayer {
+                    filter : filter,
+                    remapper : remapper,
+                    held : Vec::default()
+                });
+            }
+        }
+        Ok(built)
+    }
+}
+
+/// Modifier filter `mapping` is checked with. Mappings without modifiers exclude every modifier
+/// that another mapping of the same input among `mappings` requires.
+fn effective_filter<ACTION>(mapping : &Mapping<ACTION>, mappings : &[Mapping<ACTION>]) -> ModifierFilter
+    where ACTION: PartialEq {
+    let mut filter = mapping.raw_args.modifier_filter();
+    if filter.is_empty() {
+        for other in mappings {
+            let required = other.raw_args.modifier_filter().required;
+            if !required.is_empty() && other.same_trigger(mapping) {
+                filter.excluded = filter.excluded | required;
+            }
+        }
+    }
+    filter
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use remawin::raw;
+    use ron;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    enum TestAction {
+        Save,
+        Down
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    enum TestContext {
+        Default
+    }
+
+    /// S moves down, and Ctrl+S saves.
+    const BINDINGS : &str = "[
+        Context(
+            id: Default,
+            mappings: [
+                (raw_type: Key, raw_args: (action: Some(Press), keycode: Some(S)), action: Down),
+                (raw_type: Key, raw_args: (action: Some(Press), keycode: Some(S), modifiers: [Control]), action: Save),
+            ]
+        )
+    ]";
+
+    fn remapper() -> BindingsReMapper<TestAction, TestContext> {
+        let contexts : Vec<ContextBindings<TestAction, TestContext>> = ron::de::from_str(BINDINGS)
+            .expect("Test bindings parse");
+        let mut remapper = BindingsReMapper::from_bindings(Path::new("bindings.ron"), &contexts)
+            .expect("Test bindings are valid");
+        remapper.activate_context(&TestContext::Default, 1);
+        remapper
+    }
+
+    fn key(keycode : KeyCode, action : RawInputAction, modifiers : RawInputModifiers) -> RawInput {
+        RawInput::new(0.0, DeviceType::Keyboard, 0, RawInputEvent::Key(keycode, action, modifiers))
+    }
+
+    /// Presses and releases S with `modifiers` held, and returns the actions it mapped to.
+    fn tap_s(remapper : &mut BindingsReMapper<TestAction, TestContext>,
+             modifiers : RawInputModifiers) -> Vec<TestAction> {
+        remapper.process_raw_input(&vec![key(KeyCode::S, RawInputAction::Press, modifiers),
+                                         key(KeyCode::S, RawInputAction::Release, modifiers)])
+            .into_iter()
+            .filter_map(|event| match event {
+                remawin::Event::Controller(remawin::ControllerEvent::Action(action, _)) => Some(action),
+                _ => None
+            })
+            .collect()
+    }
+
+    #[test]
+    fn modified_press_only_fires_the_modified_mapping() {
+        let mut remapper = remapper();
+        assert_eq!(tap_s(&mut remapper, RawInputModifiers::empty()), vec![TestAction::Down]);
+        assert_eq!(tap_s(&mut remapper, raw::CONTROL), vec![TestAction::Save]);
+        assert_eq!(tap_s(&mut remapper, raw::CONTROL | raw::SHIFT), vec![TestAction::Save]);
+    }
+
+    #[test]
+    fn plain_mapping_still_fires_with_other_modifiers() {
+        let mut remapper = remapper();
+        assert_eq!(tap_s(&mut remapper, raw::SHIFT), vec![TestAction::Down]);
+    }
+}
@@ -0,0 +1,126 @@
+use amethyst::{State, Trans, WindowEvent};
+use amethyst::asset_manager::AssetManager;
+use amethyst::ecs::World;
+use amethyst::renderer::Pipeline;
+use remawin::types::ActionMetadata;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use std::hash::Hash;
+use std::cmp::Eq;
+use std::fmt::Debug;
+use std::clone::Clone;
+use std::marker::PhantomData;
+
+use input_mapper::AmethystEventMapper;
+
+/// Wraps a `State`, pushing an input context on the `AmethystEventMapper` resource when the
+/// state starts, and removing it again when the state stops. While another state is pushed
+/// over it the context is removed, and it comes back at the same priority on resume.
+pub struct ContextState<S, ACTION, ID> {
+    state : S,
+    context : ID,
+    priority : Option<u32>,
+    /// Priority the context was pushed at while it is active.
+    pushed : Option<u32>,
+    /// Priority to push the context at again when the state resumes.
+    paused : Option<u32>,
+    action : PhantomData<ACTION>
+}
+
+impl <S, ACTION, ID> ContextState<S, ACTION, ID> {
+    /// Activates `context` at `priority` while `state` runs.
+    pub fn new(state : S, context : ID, priority : u32) -> ContextState<S, ACTION, ID> {
+        ContextState {
+            state : state,
+            context : context,
+            priority : Some(priority),
+            pushed : None,
+            paused : None,
+            action : PhantomData
+        }
+    }
+
+    /// Activates `context` above every context already active while `state` runs.
+    pub fn on_top(state : S, context : ID) -> ContextState<S, ACTION, ID> {
+        ContextState {
+            state : state,
+            context : context,
+            priority : None,
+            pushed : None,
+            paused : None,
+            action : PhantomData
+        }
+    }
+}
+
+impl <S, ACTION, ID> ContextState<S, ACTION, ID>
+    where ACTION: Hash + Eq + Clone + ActionMetadata + Debug + Serialize + DeserializeOwned + Send + Sync + 'static,
+          ID: Hash + Eq + Clone + Debug + Serialize + DeserializeOwned + Send + Sync + 'static {
+
+    /// Pushes the context at `priority`, or on top when `None`, and remembers where it went.
+    fn push(&mut self, world : &mut World, priority : Option<u32>) {
+        let mut input = world.write_resource::<AmethystEventMapper<ACTION, ID>>();
+        match priority {
+            Some(priority) => input.push_context(self.context.clone(), priority),
+            None => input.push_context_on_top(self.context.clone())
+        };
+        self.pushed = input.active_contexts().last().map(|&(_, priority)| priority);
+    }
+
+    fn remove(&mut self, world : &mut World) {
+        let mut input = world.write_resource::<AmethystEventMapper<ACTION, ID>>();
+        input.remove_context(&self.context);
+    }
+}
+
+impl <S, ACTION, ID> State for ContextState<S, ACTION, ID>
+    where S: State,
+          ACTION: Hash + Eq + Clone + ActionMetadata + Debug + Serialize + DeserializeOwned + Send + Sync + 'static,
+          ID: Hash + Eq + Clone + Debug + Serialize + DeserializeOwned + Send + Sync + 'static {
+
+    fn on_start(&mut self, world : &mut World, assets : &mut AssetManager, pipe : &mut Pipeline) {
+        let priority = self.priority;
+        self.push(world, priority);
+        self.state.on_start(world, assets, pipe);
+    }
+
+    fn on_stop(&mut self, world : &mut World, assets : &mut AssetManager, pipe : &mut Pipeline) {
+        self.state.on_stop(world, assets, pipe);
+        // a paused state has already removed its context
+        if self.pushed.take().is_some() {
+            self.remove(world);
+        }
+        self.paused = None;
+    }
+
+    fn on_pause(&mut self, world : &mut World, assets : &mut AssetManager, pipe : &mut Pipeline) {
+        self.state.on_pause(world, assets, pipe);
+        self.paused = self.pushed.take();
+        self.remove(world);
+    }
+
+    fn on_resume(&mut self, world : &mut World, assets : &mut AssetManager, pipe : &mut Pipeline) {
+        let priority = self.paused.take();
+        self.push(world, priority);
+        self.state.on_resume(world, assets, pipe);
+    }
+
+    fn handle_events(&mut self,
+                     events : &[WindowEvent],
+                     world : &mut World,
+                     assets : &mut AssetManager,
+                     pipe : &mut Pipeline)
+                     -> Trans {
+        self.state.handle_events(events, world, assets, pipe)
+    }
+
+    fn fixed_update(&mut self, world : &mut World, assets : &mut AssetManager, pipe : &mut Pipeline) -> Trans {
+        self.state.fixed_update(world, assets, pipe)
+    }
+
+    fn update(&mut self, world : &mut World, assets : &mut AssetManager, pipe : &mut Pipeline) -> Trans {
+        self.state.update(world, assets, pipe)
+    }
+}
@@ -0,0 +1,45 @@
+use std::fs::{remove_file, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// A file in the temporary directory, removed again when dropped. Names are prefixed with the
+/// process id; tests running at the same time need names of their own.
+pub struct TempFile {
+    path : PathBuf
+}
+
+impl TempFile {
+    pub fn new(name : &str, data : &str) -> TempFile {
+        let path = ::std::env::temp_dir().join(format!("{}_{}", ::std::process::id(), name));
+        let file = TempFile { path : path };
+        file.write(data);
+        file
+    }
+
+    /// Path of a file that does not exist yet, removed when dropped if it was created.
+    pub fn missing(name : &str) -> TempFile {
+        let path = ::std::env::temp_dir().join(format!("{}_{}", ::std::process::id(), name));
+        let _ = remove_file(&path);
+        TempFile { path : path }
+    }
+
+    pub fn write(&self, data : &str) {
+        File::create(&self.path)
+            .and_then(|mut f| f.write_all(data.as_bytes()))
+            .expect("Writing a temporary file");
+    }
+
+    pub fn path(&self) -> &str {
+        self.path.to_str().expect("Temporary paths are UTF-8")
+    }
+
+    pub fn as_path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = remove_file(&self.path);
+    }
+}
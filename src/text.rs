@@ -0,0 +1,290 @@
+use remawin::raw::{RawInput, RawInputEvent, RawInputAction};
+use remawin::types::KeyCode;
+
+/// Text mode as of the last processed frame, kept as a resource for the systems like
+/// `InputState`. `buffer` is the text being edited, `None` outside text mode.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextInput {
+    pub buffer : Option<TextBuffer>
+}
+
+impl TextInput {
+    pub fn new() -> TextInput {
+        TextInput::default()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.buffer.is_some()
+    }
+}
+
+/// How text mode input ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextEvent {
+    /// A line submitted with Return, text mode goes on with an empty buffer.
+    Submitted(String),
+    /// Text mode left with Escape, with the buffer as it was.
+    Cancelled(TextBuffer)
+}
+
+/// An editable line of text, fed from raw keyboard input while the mapper is in text mode.
+/// The cursor is a character index into `text`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextBuffer {
+    text : String,
+    cursor : usize,
+    composition : Option<String>,
+    max_length : Option<usize>,
+    submitted : Vec<String>
+}
+
+impl TextBuffer {
+    pub fn new() -> TextBuffer {
+        TextBuffer::default()
+    }
+
+    /// A buffer starting with `text`, with the cursor at the end.
+    pub fn with_text(text : &str) -> TextBuffer {
+        let mut buffer = TextBuffer::new();
+        buffer.set_text(text);
+        buffer
+    }
+
+    /// Limits the text to `max_length` characters, cutting off any text past it. Further
+    /// input is ignored.
+    pub fn with_max_length(mut self, max_length : usize) -> TextBuffer {
+        self.max_length = Some(max_length);
+        let text = self.text.clone();
+        self.set_text(&text);
+        self
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Text being composed by an input method, shown at the cursor but not yet part of the text.
+    pub fn composition(&self) -> Option<&str> {
+        self.composition.as_ref().map(|c| c.as_str())
+    }
+
+    /// Replaces the text, cut off at the maximum length, and moves the cursor to the end.
+    pub fn set_text(&mut self, text : &str) {
+        self.text = match self.max_length {
+            Some(max_length) => text.chars().take(max_length).collect(),
+            None => text.to_string()
+        };
+        self.cursor = self.len();
+        self.composition = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.set_text("");
+    }
+
+    /// Sets the in-progress input method composition. glutin only delivers committed
+    /// characters, so this is for platform IME hooks; the committed characters arriving as
+    /// `Char` input end the composition.
+    pub fn set_composition(&mut self, composition : Option<String>) {
+        self.composition = composition.and_then(|c| if c.is_empty() { None } else { Some(c) });
+    }
+
+    /// Lines submitted with Return since the last call.
+    pub fn take_submitted(&mut self) -> Vec<String> {
+        self.submitted.drain(..).collect()
+    }
+
+    pub fn insert(&mut self, ch : char) {
+        if let Some(max_length) = self.max_length {
+            if self.len() >= max_length {
+                return;
+            }
+        }
+        let index = self.byte_index(self.cursor);
+        self.text.insert(index, ch);
+        self.cursor += 1;
+    }
+
+    /// Removes the character before the cursor.
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            let index = self.byte_index(self.cursor);
+            self.text.remove(index);
+        }
+    }
+
+    /// Removes the character after the cursor.
+    pub fn delete(&mut self) {
+        if self.cursor < self.len() {
+            let index = self.byte_index(self.cursor);
+            self.text.remove(index);
+        }
+    }
+
+    pub fn move_left(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if self.cursor < self.len() {
+            self.cursor += 1;
+        }
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.len();
+    }
+
+    /// Moves the current text to the submitted lines and clears the buffer.
+    pub fn submit(&mut self) {
+        let text = self.text.clone();
+        self.submitted.push(text);
+        self.clear();
+    }
+
+    /// Applies one raw input event, returning false for input that is not text editing.
+    pub fn process(&mut self, raw : &RawInput) -> bool {
+        match raw.event {
+            RawInputEvent::Char(ch) => {
+                // glutin also sends control characters for Backspace, Return and friends,
+                // those are handled from the key input instead
+                if !ch.is_control() {
+                    self.composition = None;
+                    self.insert(ch);
+                }
+                true
+            },
+            RawInputEvent::Key(ref keycode, RawInputAction::Press, _) => {
+                match *keycode {
+                    KeyCode::Back => self.backspace(),
+                    KeyCode::Delete => self.delete(),
+                    KeyCode::Left => self.move_left(),
+                    KeyCode::Right => self.move_right(),
+                    KeyCode::Home => self.move_home(),
+                    KeyCode::End => self.move_end(),
+                    KeyCode::Return => self.submit(),
+                    _ => ()
+                };
+                true
+            },
+            RawInputEvent::Key(..) => true,
+            _ => false
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    fn byte_index(&self, cursor : usize) -> usize {
+        self.text.char_indices()
+            .nth(cursor)
+            .map(|(index, _)| index)
+            .unwrap_or(self.text.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use remawin::raw::RawInputModifiers;
+    use remawin::types::DeviceType;
+
+    fn press(buffer : &mut TextBuffer, keycode : KeyCode) {
+        let event = RawInputEvent::Key(keycode, RawInputAction::Press, RawInputModifiers::empty());
+        assert!(buffer.process(&RawInput::new(0.0, DeviceType::Keyboard, 0, event)));
+    }
+
+    fn type_text(buffer : &mut TextBuffer, text : &str) {
+        for ch in text.chars() {
+            assert!(buffer.process(&RawInput::new(0.0, DeviceType::Keyboard, 0, RawInputEvent::Char(ch))));
+        }
+    }
+
+    #[test]
+    fn editing_moves_by_characters_not_bytes() {
+        let mut buffer = TextBuffer::with_text("grüße");
+        assert_eq!(buffer.cursor(), 5);
+        press(&mut buffer, KeyCode::Back);
+        press(&mut buffer, KeyCode::Left);
+        press(&mut buffer, KeyCode::Back);
+        assert_eq!((buffer.text(), buffer.cursor()), ("grß", 2));
+        type_text(&mut buffer, "ö");
+        press(&mut buffer, KeyCode::Delete);
+        assert_eq!((buffer.text(), buffer.cursor()), ("grö", 3));
+
+        press(&mut buffer, KeyCode::Home);
+        press(&mut buffer, KeyCode::Delete);
+        press(&mut buffer, KeyCode::Right);
+        type_text(&mut buffer, "日本");
+        assert_eq!((buffer.text(), buffer.cursor()), ("r日本ö", 3));
+        press(&mut buffer, KeyCode::End);
+        press(&mut buffer, KeyCode::Right);
+        press(&mut buffer, KeyCode::Delete);
+        press(&mut buffer, KeyCode::Back);
+        assert_eq!((buffer.text(), buffer.cursor()), ("r日本", 3));
+    }
+
+    #[test]
+    fn editing_stops_at_the_ends() {
+        let mut buffer = TextBuffer::new();
+        press(&mut buffer, KeyCode::Back);
+        press(&mut buffer, KeyCode::Delete);
+        press(&mut buffer, KeyCode::Left);
+        press(&mut buffer, KeyCode::Right);
+        assert_eq!((buffer.text(), buffer.cursor()), ("", 0));
+    }
+
+    #[test]
+    fn max_length_cuts_off_text_and_ignores_more_input() {
+        let mut buffer = TextBuffer::with_text("ñandú").with_max_length(3);
+        assert_eq!((buffer.text(), buffer.cursor()), ("ñan", 3));
+        type_text(&mut buffer, "x");
+        press(&mut buffer, KeyCode::Home);
+        type_text(&mut buffer, "x");
+        assert_eq!(buffer.text(), "ñan");
+        buffer.set_text("ünïcode");
+        assert_eq!(buffer.text(), "ünï");
+        press(&mut buffer, KeyCode::Back);
+        type_text(&mut buffer, "ëx");
+        assert_eq!(buffer.text(), "ünë");
+    }
+
+    #[test]
+    fn composition_is_replaced_until_committed() {
+        let mut buffer = TextBuffer::with_text("a");
+        buffer.set_composition(Some("k".to_string()));
+        buffer.set_composition(Some("か".to_string()));
+        assert_eq!((buffer.text(), buffer.composition()), ("a", Some("か")));
+        type_text(&mut buffer, "か");
+        assert_eq!((buffer.text(), buffer.composition()), ("aか", None));
+
+        buffer.set_composition(Some("n".to_string()));
+        buffer.set_composition(Some(String::new()));
+        assert_eq!(buffer.composition(), None);
+        buffer.set_composition(Some("n".to_string()));
+        buffer.set_text("b");
+        assert_eq!((buffer.text(), buffer.composition()), ("b", None));
+    }
+
+    #[test]
+    fn control_characters_and_return_do_not_insert() {
+        let mut buffer = TextBuffer::with_text("hi");
+        type_text(&mut buffer, "\u{8}\r");
+        press(&mut buffer, KeyCode::Return);
+        assert_eq!((buffer.text(), buffer.cursor()), ("", 0));
+        assert_eq!(buffer.take_submitted(), vec!["hi".to_string()]);
+        assert!(buffer.take_submitted().is_empty());
+    }
+}
@@ -0,0 +1,353 @@
+use glutin::{Touch, TouchPhase};
+use remawin::raw::{RawInput, RawInputEvent, RawInputAction, RawInputModifiers};
+use remawin::types::DeviceType;
+use ron;
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use bindings::{BindingsError, BindingsIssue, list_item_lines, parse_error};
+use cursor::normalise;
+
+/// Button pressed while a finger touches the screen, on the touch device with the finger id.
+pub const TOUCH_BUTTON : u32 = 120;
+
+/// Touch device id of the first touch region, the others follow in the order they were added.
+/// Finger ids are kept below it, so region input never shares a device with a finger.
+pub const TOUCH_REGION_DEVICE_BASE : u32 = 0x8000_0000;
+
+/// A screen rectangle in [0, 1] window coordinates, from the top left corner, that holds
+/// `button` down while at least one finger is inside it. Each region is its own touch device,
+/// reporting the position of the finger that has been inside the longest. Bind the button
+/// like any other to make parts of a touchscreen act as controls.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TouchRegion {
+    pub button : u32,
+    pub left : f64,
+    pub top : f64,
+    pub right : f64,
+    pub bottom : f64
+}
+
+impl TouchRegion {
+    pub fn contains(&self, position : (f64, f64)) -> bool {
+        position.0 >= self.left && position.0 < self.right
+            && position.1 >= self.top && position.1 < self.bottom
+    }
+
+    fn overlaps(&self, other : &TouchRegion) -> bool {
+        self.left < other.right && other.left < self.right
+            && self.top < other.bottom && other.top < self.bottom
+    }
+}
+
+/// Reads a list of touch regions from a RON file. Regions must lie within the window and
+/// must not overlap, so one finger never holds two of them down.
+pub fn load_touch_regions<P>(path : P) -> Result<Vec<TouchRegion>, BindingsError>
+    where P: AsRef<Path> {
+    let path = path.as_ref();
+    let mut data = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut data))
+        .map_err(|err| BindingsError::Io(path.to_path_buf(), err))?;
+    let regions : Vec<TouchRegion> = ron::de::from_str(&data)
+        .map_err(|err| parse_error(path, err))?;
+    let lines = list_item_lines(&data);
+    let mut issues = Vec::default();
+    for (i, region) in regions.iter().enumerate() {
+        issues.extend(region_problems(region, &regions[..i]).into_iter().map(|message| BindingsIssue {
+            path : path.to_path_buf(),
+            line : lines.get(i).cloned(),
+            context : String::new(),
+            message : message
+        }));
+    }
+    if issues.len() > 0 {
+        Err(BindingsError::Invalid(issues))
+    } else {
+        Ok(regions)
+    }
+}
+
+/// Problems with adding `region` to the regions `others`.
+fn region_problems(region : &TouchRegion, others : &[TouchRegion]) -> Vec<String> {
+    let mut problems = Vec::default();
+    let inside = 0.0 <= region.left && region.left < region.right && region.right <= 1.0
+        && 0.0 <= region.top && region.top < region.bottom && region.bottom <= 1.0;
+    if !inside {
+        problems.push("is empty or not within [0, 1]".to_string());
+    }
+    for other in others {
+        if region.overlaps(other) {
+            problems.push(format!("overlaps the region of button {}", other.button));
+        }
+    }
+    problems.into_iter()
+        .map(|problem| format!("touch region of button {}: {}", region.button, problem))
+        .collect()
+}
+
+/// Fingers currently on the screen, in the order they went down, and the touch regions they
+/// hold down.
+#[derive(Debug, Default)]
+pub struct TouchState {
+    fingers : Vec<(u64, (f64, f64))>,
+    regions : Vec<TouchRegion>
+}
+
+impl TouchState {
+    pub fn new() -> TouchState {
+        TouchState {
+            fingers : Vec::default(),
+            regions : Vec::default()
+        }
+    }
+
+    /// Adds `regions`, unless one of them is not within the window or overlaps another region.
+    /// Then nothing is added, and the problems found are returned.
+    pub fn add_regions(&mut self, regions : Vec<TouchRegion>) -> Result<(), Vec<String>> {
+        let mut all = self.regions.clone();
+        let mut problems = Vec::default();
+        for region in regions {
+            problems.extend(region_problems(&region, &all));
+            all.push(region);
+        }
+        if problems.len() > 0 {
+            return Err(problems);
+        }
+        self.regions = all;
+        Ok(())
+    }
+
+    pub fn clear_regions(&mut self) {
+        self.regions.clear();
+    }
+
+    /// Maps a touch, with its location in pixels in a window of `size`, to raw input. A finger
+    /// reports a `TOUCH_BUTTON` press when it goes down, its normalised position and motion as
+    /// it moves, and a release when it is lifted or the touch is cancelled. While `size` is not
+    /// valid, fingers keep their last position so they can still be lifted.
+    pub fn process_touch(&mut self, touch : &Touch, size : (f64, f64), t : f64) -> Vec<RawInput> {
+        let index = self.fingers.iter().position(|&(id, _)| id == touch.id);
+        let position = normalise(touch.location, size).or(index.map(|index| self.fingers[index].1));
+        let position = match position {
+            Some(position) => position,
+            None => return Vec::default()
+        };
+        let device_id = touch.id as u32 & !TOUCH_REGION_DEVICE_BASE;
+        let held_before = self.region_positions();
+        let mut raw = Vec::new();
+        match (touch.phase, index) {
+            (TouchPhase::Started, _) => {
+                if let Some(index) = index {
+                    self.fingers.remove(index);
+                }
+                self.fingers.push((touch.id, position));
+                raw.push(RawInput::new(t, DeviceType::Touch, device_id,
+                                       RawInputEvent::CursorPosition(position.0, position.1)));
+                raw.push(touch_button(t, device_id, TOUCH_BUTTON, position, RawInputAction::Press));
+            },
+            (TouchPhase::Moved, Some(index)) => {
+                let (px, py) = self.fingers[index].1;
+                self.fingers[index].1 = position;
+                raw.push(RawInput::new(t, DeviceType::Touch, device_id,
+                                       RawInputEvent::CursorPosition(position.0, position.1)));
+                raw.push(RawInput::new(t, DeviceType::Touch, device_id,
+                                       RawInputEvent::Motion(position.0 - px, position.1 - py)));
+            },
+            (TouchPhase::Ended, Some(index)) | (TouchPhase::Cancelled, Some(index)) => {
+                self.fingers.remove(index);
+                raw.push(touch_button(t, device_id, TOUCH_BUTTON, position, RawInputAction::Release));
+            },
+            _ => ()
+        };
+
+        raw.extend(self.region_changes(&held_before, t));
+        raw
+    }
+
+    /// Lifts every finger, releasing the touch regions they held, for when the window loses
+    /// focus and the touches ending will not be reported.
+    pub fn release_all(&mut self, t : f64) -> Vec<RawInput> {
+        let held_before = self.region_positions();
+        let mut raw : Vec<RawInput> = self.fingers.drain(..)
+            .map(|(id, position)| {
+                let device_id = id as u32 & !TOUCH_REGION_DEVICE_BASE;
+                touch_button(t, device_id, TOUCH_BUTTON, position, RawInputAction::Release)
+            })
+            .collect();
+        raw.extend(self.region_changes(&held_before, t));
+        raw
+    }
+
+    /// Input for the regions whose fingers changed since they were at `held_before`.
+    fn region_changes(&self, held_before : &[Option<(f64, f64)>], t : f64) -> Vec<RawInput> {
+        // regions report a press when the first finger enters, the position of their oldest
+        // finger as it changes, and a release when the last leaves
+        let mut raw = Vec::new();
+        let held_after = self.region_positions();
+        for (i, region) in self.regions.iter().enumerate() {
+            let device_id = TOUCH_REGION_DEVICE_BASE + i as u32;
+            match (held_before[i], held_after[i]) {
+                (Some(before), None) =>
+                    raw.push(touch_button(t, device_id, region.button, before, RawInputAction::Release)),
+                (None, Some(after)) => {
+                    raw.push(RawInput::new(t, DeviceType::Touch, device_id,
+                                           RawInputEvent::CursorPosition(after.0, after.1)));
+                    raw.push(touch_button(t, device_id, region.button, after, RawInputAction::Press));
+                },
+                (Some(before), Some(after)) => if before != after {
+                    raw.push(RawInput::new(t, DeviceType::Touch, device_id,
+                                           RawInputEvent::CursorPosition(after.0, after.1)));
+                },
+                (None, None) => ()
+            };
+        }
+        raw
+    }
+
+    /// Position of the oldest finger in each region, if any.
+    fn region_positions(&self) -> Vec<Option<(f64, f64)>> {
+        self.regions.iter()
+            .map(|region| self.fingers.iter()
+                .map(|&(_, position)| position)
+                .find(|&position| region.contains(position)))
+            .collect()
+    }
+}
+
+fn touch_button(t : f64, device_id : u32, button : u32, position : (f64, f64), action : RawInputAction) -> RawInput {
+    RawInput::new(t, DeviceType::Touch, device_id,
+                  RawInputEvent::Button(button, position, action, RawInputModifiers::empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE : (f64, f64) = (100.0, 100.0);
+
+    fn touch(phase : TouchPhase, x : f64, y : f64, id : u64) -> Touch {
+        Touch {
+            phase : phase,
+            location : (x, y),
+            id : id
+        }
+    }
+
+    fn halves() -> TouchState {
+        let mut state = TouchState::new();
+        state.add_regions(vec![TouchRegion { button : 122, left : 0.0, top : 0.0, right : 0.5, bottom : 1.0 },
+                               TouchRegion { button : 123, left : 0.5, top : 0.0, right : 1.0, bottom : 1.0 }])
+            .expect("The halves of the window do not overlap");
+        state
+    }
+
+    /// Device id, button and action of touch button input.
+    fn buttons(raw_input : &[RawInput]) -> Vec<(u32, u32, RawInputAction)> {
+        raw_input.iter()
+            .filter_map(|raw| match raw.event {
+                RawInputEvent::Button(button, _, ref action, _) => Some((raw.device_id, button, action.clone())),
+                _ => None
+            })
+            .collect()
+    }
+
+    /// Device id and position of touch position input.
+    fn positions(raw_input : &[RawInput]) -> Vec<(u32, (f64, f64))> {
+        raw_input.iter()
+            .filter_map(|raw| match raw.event {
+                RawInputEvent::CursorPosition(x, y) => Some((raw.device_id, (x, y))),
+                _ => None
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fingers_and_regions_use_separate_devices() {
+        let mut state = halves();
+        let raw = state.process_touch(&touch(TouchPhase::Started, 20.0, 50.0, 0), SIZE, 1.0);
+        assert_eq!(buttons(&raw), vec![(0, TOUCH_BUTTON, RawInputAction::Press),
+                                       (TOUCH_REGION_DEVICE_BASE, 122, RawInputAction::Press)]);
+        let raw = state.process_touch(&touch(TouchPhase::Started, 80.0, 50.0, 1), SIZE, 1.0);
+        assert_eq!(buttons(&raw), vec![(1, TOUCH_BUTTON, RawInputAction::Press),
+                                       (TOUCH_REGION_DEVICE_BASE + 1, 123, RawInputAction::Press)]);
+    }
+
+    #[test]
+    fn finger_ids_stay_below_region_devices() {
+        let mut state = halves();
+        let raw = state.process_touch(&touch(TouchPhase::Started, 20.0, 50.0, 0x8000_0005), SIZE, 1.0);
+        assert_eq!(buttons(&raw)[0], (5, TOUCH_BUTTON, RawInputAction::Press));
+    }
+
+    #[test]
+    fn region_follows_its_oldest_finger() {
+        let mut state = halves();
+        state.process_touch(&touch(TouchPhase::Started, 10.0, 20.0, 0), SIZE, 1.0);
+        state.process_touch(&touch(TouchPhase::Started, 30.0, 60.0, 1), SIZE, 1.0);
+        let raw = state.process_touch(&touch(TouchPhase::Moved, 10.0, 40.0, 0), SIZE, 1.0);
+        assert_eq!(positions(&raw), vec![(0, (0.1, 0.4)), (TOUCH_REGION_DEVICE_BASE, (0.1, 0.4))]);
+
+        // the other finger moving does not move the region
+        let raw = state.process_touch(&touch(TouchPhase::Moved, 30.0, 70.0, 1), SIZE, 1.0);
+        assert_eq!(positions(&raw), vec![(1, (0.3, 0.7))]);
+
+        // once the oldest finger is lifted the region follows the next one, still held
+        let raw = state.process_touch(&touch(TouchPhase::Ended, 10.0, 40.0, 0), SIZE, 1.0);
+        assert_eq!(buttons(&raw), vec![(0, TOUCH_BUTTON, RawInputAction::Release)]);
+        assert_eq!(positions(&raw), vec![(TOUCH_REGION_DEVICE_BASE, (0.3, 0.7))]);
+
+        let raw = state.process_touch(&touch(TouchPhase::Cancelled, 30.0, 70.0, 1), SIZE, 1.0);
+        assert_eq!(buttons(&raw), vec![(1, TOUCH_BUTTON, RawInputAction::Release),
+                                       (TOUCH_REGION_DEVICE_BASE, 122, RawInputAction::Release)]);
+    }
+
+    #[test]
+    fn finger_moving_across_regions_releases_one_and_presses_the_other() {
+        let mut state = halves();
+        state.process_touch(&touch(TouchPhase::Started, 40.0, 50.0, 0), SIZE, 1.0);
+        let raw = state.process_touch(&touch(TouchPhase::Moved, 60.0, 50.0, 0), SIZE, 1.0);
+        assert_eq!(buttons(&raw), vec![(TOUCH_REGION_DEVICE_BASE, 122, RawInputAction::Release),
+                                       (TOUCH_REGION_DEVICE_BASE + 1, 123, RawInputAction::Press)]);
+    }
+
+    #[test]
+    fn lifting_an_unknown_finger_reports_nothing() {
+        let mut state = halves();
+        assert!(state.process_touch(&touch(TouchPhase::Ended, 40.0, 50.0, 3), SIZE, 1.0).is_empty());
+    }
+
+    #[test]
+    fn regions_must_be_inside_the_window_and_apart() {
+        let mut state = halves();
+        let corner = TouchRegion { button : 124, left : 0.75, top : 0.75, right : 1.25, bottom : 1.0 };
+        let empty = TouchRegion { button : 125, left : 0.5, top : 0.5, right : 0.5, bottom : 0.75 };
+        assert_eq!(state.add_regions(vec![corner, empty]),
+                   Err(vec!["touch region of button 124: is empty or not within [0, 1]".to_string(),
+                            "touch region of button 124: overlaps the region of button 123".to_string(),
+                            "touch region of button 125: is empty or not within [0, 1]".to_string()]));
+        // nothing was added, so a finger in the corner only holds the right half
+        let raw = state.process_touch(&touch(TouchPhase::Started, 90.0, 90.0, 0), SIZE, 1.0);
+        assert_eq!(buttons(&raw), vec![(0, TOUCH_BUTTON, RawInputAction::Press),
+                                       (TOUCH_REGION_DEVICE_BASE + 1, 123, RawInputAction::Press)]);
+    }
+
+    #[test]
+    fn releasing_all_lifts_fingers_and_regions() {
+        let mut state = halves();
+        state.process_touch(&touch(TouchPhase::Started, 20.0, 50.0, 0), SIZE, 1.0);
+        state.process_touch(&touch(TouchPhase::Started, 30.0, 50.0, 1), SIZE, 1.0);
+        state.process_touch(&touch(TouchPhase::Started, 80.0, 50.0, 2), SIZE, 1.0);
+        let raw = state.release_all(2.0);
+        assert_eq!(buttons(&raw), vec![(0, TOUCH_BUTTON, RawInputAction::Release),
+                                       (1, TOUCH_BUTTON, RawInputAction::Release),
+                                       (2, TOUCH_BUTTON, RawInputAction::Release),
+                                       (TOUCH_REGION_DEVICE_BASE, 122, RawInputAction::Release),
+                                       (TOUCH_REGION_DEVICE_BASE + 1, 123, RawInputAction::Release)]);
+        assert!(state.release_all(2.0).is_empty());
+        // the fingers are gone, so lifting them later reports nothing
+        assert!(state.process_touch(&touch(TouchPhase::Ended, 20.0, 50.0, 0), SIZE, 2.5).is_empty());
+    }
+}
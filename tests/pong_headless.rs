@@ -0,0 +1,199 @@
+extern crate amethyst;
+extern crate remawin;
+extern crate cgmath;
+extern crate shrev;
+extern crate rand;
+extern crate serde;
+extern crate ron;
+extern crate amethyst_experiments;
+
+#[macro_use]
+extern crate serde_derive;
+
+use amethyst::ecs::Join;
+use cgmath::{InnerSpace, Point2, Vector2};
+use remawin::raw::{RawInput, RawInputEvent, RawInputAction, RawInputModifiers};
+use remawin::types::{DeviceType, KeyCode};
+
+use amethyst_experiments::AmethystEventMapper;
+use amethyst_experiments::recording::InputRecording;
+
+use comp::Ball;
+use config::{ControlConfig, RulesConfig};
+use headless::Headless;
+use input::{init_input_system, Action, InputContext};
+
+// the tests run the pong example's world, systems and bindings
+#[path = "../02_pong/input.rs"]
+#[allow(dead_code)]
+mod input;
+#[path = "../02_pong/config.rs"]
+#[allow(dead_code)]
+mod config;
+#[path = "../02_pong/comp.rs"]
+#[allow(dead_code)]
+mod comp;
+#[path = "../02_pong/event.rs"]
+#[allow(dead_code)]
+mod event;
+#[path = "../02_pong/system.rs"]
+#[allow(dead_code)]
+mod system;
+#[path = "../02_pong/setup.rs"]
+mod setup;
+#[path = "../02_pong/headless.rs"]
+#[allow(dead_code)]
+mod headless;
+
+const WINDOW_SIZE : (f64, f64) = (1024.0, 768.0);
+
+fn resource(name : &str) -> String {
+    format!("{}/02_pong/resources/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn input() -> AmethystEventMapper<Action, InputContext> {
+    init_input_system(WINDOW_SIZE,
+                      &resource("bindings.ron"),
+                      &resource("bindings_player.ron"),
+                      &resource("bindings_user.ron"),
+                      &resource("combos.ron"),
+                      &resource("axes.ron"),
+                      &resource("axes_user.ron"),
+                      &resource("touch_regions.ron"))
+}
+
+fn rules() -> RulesConfig {
+    RulesConfig::load(&resource("rules.ron"))
+}
+
+/// A match with both planks on their axes, which nobody moves.
+fn headless() -> Headless {
+    Headless::new(WINDOW_SIZE, input(), ControlConfig::default(), rules())
+}
+
+fn tick_length() -> f64 {
+    rules().tick_length() as f64
+}
+
+fn space(t : f64, action : RawInputAction) -> RawInput {
+    RawInput::new(t, DeviceType::Keyboard, 0,
+                  RawInputEvent::Key(KeyCode::Space, action, RawInputModifiers::empty()))
+}
+
+/// Puts the ball at `position` moving with `velocity`, without serving.
+fn place_ball(headless : &mut Headless, position : (f32, f32), velocity : (f32, f32)) {
+    for ball in (&mut headless.world_mut().write::<Ball>()).join() {
+        ball.position = Point2::new(position.0, position.1);
+        ball.previous_position = ball.position;
+        ball.velocity = Vector2::new(velocity.0, velocity.1);
+    }
+}
+
+fn ball(headless : &Headless) -> (Point2<f32>, Vector2<f32>) {
+    let balls = headless.world().read::<Ball>();
+    let ball = balls.join().next().expect("The ball was created with the world");
+    (ball.position, ball.velocity)
+}
+
+fn score(headless : &Headless) -> (u32, u32) {
+    let game_state = headless.game_state();
+    (game_state.left_score, game_state.right_score)
+}
+
+#[test]
+fn nothing_happens_without_a_serve() {
+    let mut headless = headless();
+    assert_eq!(headless.run(240), 240);
+    assert_eq!(score(&headless), (0, 0));
+    assert!(!headless.game_state().round_active);
+    assert_eq!(ball(&headless).0, Point2::new(0.0, 0.0));
+}
+
+#[test]
+fn releasing_space_serves() {
+    let mut headless = headless();
+    headless
+        .push_raw_input(0, vec![space(0.0, RawInputAction::Press)])
+        .push_raw_input(1, vec![space(tick_length(), RawInputAction::Release)]);
+    headless.run(1);
+    assert!(!headless.game_state().round_active);
+    headless.run(2);
+    assert!(headless.game_state().round_active);
+    assert!((ball(&headless).1.magnitude() - 0.6).abs() < 1.0e-4);
+}
+
+#[test]
+fn controller_events_serve() {
+    // map the serve with a mapper of its own, and hand the event straight to the game
+    let mut mapper = input();
+    let mapped = mapper.process_raw_input_at(&vec![space(0.0, RawInputAction::Press),
+                                                   space(0.0, RawInputAction::Release)],
+                                             0.0);
+    let (player, event) = mapped.into_iter()
+        .filter_map(|(player, event)| match event {
+            remawin::Event::Controller(event @ remawin::ControllerEvent::Action(Action::StartRound, _)) =>
+                Some((player, event)),
+            _ => None
+        })
+        .next()
+        .expect("Releasing space maps to StartRound");
+
+    let mut headless = headless();
+    headless.push_event(10, player, event);
+    headless.run(10);
+    assert!(!headless.game_state().round_active);
+    headless.run(2);
+    assert!(headless.game_state().round_active);
+}
+
+#[test]
+fn ball_past_the_right_plank_scores_for_the_left_player() {
+    // above the plank, which reaches 0.15 up from the centre, and 4/3 from the right edge
+    let mut headless = headless();
+    place_ball(&mut headless, (0.0, 0.8), (1.0, 0.0));
+    headless.run(150);
+    assert_eq!(score(&headless), (0, 0));
+    headless.run(30);
+    assert_eq!(score(&headless), (1, 0));
+    assert_eq!(headless.game_state().round, 2);
+    assert_eq!(ball(&headless), (Point2::new(0.0, 0.0), Vector2::new(0.0, 0.0)));
+}
+
+#[test]
+fn ball_past_the_left_plank_scores_for_the_right_player() {
+    let mut headless = headless();
+    place_ball(&mut headless, (0.0, -0.8), (-1.0, 0.0));
+    headless.run(180);
+    assert_eq!(score(&headless), (0, 1));
+}
+
+#[test]
+fn left_plank_returns_the_ball() {
+    // hits the middle of the plank after about 1.3s, and leaves flat and 1.2 times as fast
+    let mut headless = headless();
+    place_ball(&mut headless, (0.0, 0.0), (-1.0, 0.0));
+    headless.run(300);
+    assert_eq!(score(&headless), (0, 0));
+    let (position, velocity) = ball(&headless);
+    assert!(position.x > -1.3);
+    assert!((velocity.x - 1.2).abs() < 1.0e-4 && velocity.y.abs() < 1.0e-4,
+            "expected the ball to leave flat at 1.2, it moves at {:?}", velocity);
+}
+
+#[test]
+fn replay_plays_back_by_recorded_time() {
+    // space is held for 0.7s, long enough for a power serve, on a clock that does not start at 0
+    let mut recording = InputRecording::new();
+    recording.push_frame(10.0, vec![space(10.0, RawInputAction::Press)]);
+    recording.push_frame(10.7, vec![space(10.7, RawInputAction::Release)]);
+
+    let mut headless = headless();
+    headless.replay(recording);
+    headless.run(80);
+    assert!(headless.game_state().power_serve);
+    assert!(!headless.game_state().round_active);
+    headless.run(10);
+    assert!(headless.game_state().round_active);
+    assert!(!headless.game_state().power_serve);
+    assert!((ball(&headless).1.magnitude() - 0.9).abs() < 1.0e-4);
+}